@@ -0,0 +1,238 @@
+//! Parsing for `#[ptrpack(...)]` attributes.
+
+use proc_macro2::Span;
+use syn::{parse_quote, Attribute, Error, Expr, Ident, Lit, Meta, NestedMeta, Path};
+
+/// Collect the items within all `#[ptrpack(...)]` attributes.
+fn ptrpack_metas(attrs: &[Attribute]) -> Result<Vec<NestedMeta>, Error> {
+    let mut metas = Vec::new();
+    for attr in attrs {
+        if !attr.path.is_ident("ptrpack") {
+            continue;
+        }
+        match attr.parse_meta()? {
+            Meta::List(list) => metas.extend(list.nested),
+            meta => return Err(Error::new_spanned(meta, "expected `#[ptrpack(...)]`")),
+        }
+    }
+    Ok(metas)
+}
+
+/// Find the integer type named by a `#[repr(...)]` attribute, if any.
+fn discr_repr(attrs: &[Attribute]) -> Result<Option<Ident>, Error> {
+    const INTS: &[&str] = &[
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+    ];
+    for attr in attrs {
+        if !attr.path.is_ident("repr") {
+            continue;
+        }
+        if let Meta::List(list) = attr.parse_meta()? {
+            for nested in &list.nested {
+                if let NestedMeta::Meta(Meta::Path(path)) = nested {
+                    if let Some(ident) = path.get_ident() {
+                        if INTS.iter().any(|ty| ident == ty) {
+                            return Ok(Some(ident.clone()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Where to place the discriminant of an enum.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum TagPlacement {
+    /// After the variant payloads, in the least significant bits.
+    Low,
+    /// Before the variant payloads, in the most significant bits.
+    High,
+    /// Below the variant payloads, with its least significant bit at the
+    /// given bit index of the `usize`, regardless of where the enum is
+    /// packed. Any bits below the tag are left for later fields.
+    Bit(u32),
+    /// Before the variant payloads, using a variable-length prefix code.
+    /// Tags are assigned from the width of each variant's payload, so that
+    /// narrower variants use longer tags.
+    Prefix,
+}
+
+/// Options which may be specified on the type being derived.
+#[derive(Default)]
+pub struct ContainerAttrs {
+    /// `#[ptrpack(tag = "low" | "high" | "prefix" | "bit:N")]`: Where to
+    /// place the discriminant of an enum.
+    pub tag: Option<(TagPlacement, Span)>,
+    /// `#[ptrpack(niche)]`: Store the unit variants of an enum as small
+    /// invalid values of the payload of its only data-carrying variant.
+    pub niche: Option<Span>,
+    /// `#[ptrpack(transparent)]`: Pack a single-field struct exactly like its
+    /// field, using `SubPack` rather than a generated helper as its `Packed`
+    /// type.
+    pub transparent: Option<Span>,
+    /// `#[ptrpack(legacy_setters)]`: Keep the deprecated `set_` methods on
+    /// struct helpers returning a mutable reference to the field, instead of
+    /// ones taking the new value.
+    pub legacy_setters: Option<Span>,
+    /// `#[ptrpack(crate = "path")]`: The path to the `ptrpack` crate, for use
+    /// when it has been renamed or re-exported.
+    pub krate: Option<Path>,
+    /// `#[ptrpack(repr = "u16")]`: Also generate a bitfield type wrapping the
+    /// given unsigned integer type.
+    pub repr: Option<Ident>,
+    /// `#[repr(i8)]` and other integer reprs of an enum, which determine how
+    /// negative discriminants are stored.
+    pub discr_repr: Option<Ident>,
+}
+
+impl ContainerAttrs {
+    /// The path used to refer to the `ptrpack` crate in generated code.
+    pub fn krate(&self) -> Path {
+        match &self.krate {
+            Some(path) => path.clone(),
+            None => parse_quote!(::ptrpack),
+        }
+    }
+
+    pub fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut result = ContainerAttrs {
+            discr_repr: discr_repr(attrs)?,
+            ..ContainerAttrs::default()
+        };
+        for meta in ptrpack_metas(attrs)? {
+            match &meta {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("tag") => {
+                    if result.tag.is_some() {
+                        return Err(Error::new_spanned(nv, "duplicate `tag` attribute"));
+                    }
+                    let value = match &nv.lit {
+                        Lit::Str(lit) => lit.value(),
+                        _ => String::new(),
+                    };
+                    let bit = value.strip_prefix("bit:").map(|bit| bit.parse());
+                    let placement = match (value.as_str(), bit) {
+                        ("low", _) => TagPlacement::Low,
+                        ("high", _) => TagPlacement::High,
+                        ("prefix", _) => TagPlacement::Prefix,
+                        (_, Some(Ok(bit))) => TagPlacement::Bit(bit),
+                        _ => {
+                            return Err(Error::new_spanned(
+                                &nv.lit,
+                                "expected `\"low\"`, `\"high\"`, `\"prefix\"` or `\"bit:N\"`",
+                            ))
+                        }
+                    };
+                    result.tag = Some((placement, nv.lit.span()));
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("niche") => {
+                    if result.niche.is_some() {
+                        return Err(Error::new_spanned(path, "duplicate `niche` attribute"));
+                    }
+                    result.niche = Some(path.get_ident().unwrap().span());
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                    if result.krate.is_some() {
+                        return Err(Error::new_spanned(nv, "duplicate `crate` attribute"));
+                    }
+                    result.krate = match &nv.lit {
+                        Lit::Str(lit) => Some(lit.parse()?),
+                        lit => return Err(Error::new_spanned(lit, "expected a string")),
+                    };
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("repr") => {
+                    if result.repr.is_some() {
+                        return Err(Error::new_spanned(nv, "duplicate `repr` attribute"));
+                    }
+                    let repr = match &nv.lit {
+                        Lit::Str(lit) => lit.parse::<Ident>()?,
+                        lit => return Err(Error::new_spanned(lit, "expected a string")),
+                    };
+                    if !["u8", "u16", "u32", "u64", "usize"]
+                        .iter()
+                        .any(|ty| repr == ty)
+                    {
+                        return Err(Error::new_spanned(
+                            &nv.lit,
+                            "expected an unsigned integer type",
+                        ));
+                    }
+                    result.repr = Some(repr);
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("transparent") => {
+                    if result.transparent.is_some() {
+                        return Err(Error::new_spanned(
+                            path,
+                            "duplicate `transparent` attribute",
+                        ));
+                    }
+                    result.transparent = Some(path.get_ident().unwrap().span());
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("legacy_setters") => {
+                    if result.legacy_setters.is_some() {
+                        return Err(Error::new_spanned(
+                            path,
+                            "duplicate `legacy_setters` attribute",
+                        ));
+                    }
+                    result.legacy_setters = Some(path.get_ident().unwrap().span());
+                }
+                _ => return Err(Error::new_spanned(meta, "unknown ptrpack attribute")),
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Options which may be specified on a field.
+#[derive(Default)]
+pub struct FieldAttrs {
+    /// `#[ptrpack(bits = N)]`: Store an integer field using only `N` bits.
+    pub bits: Option<u32>,
+    /// `#[ptrpack(skip)]` or `#[ptrpack(default = "expr")]`: Don't store the
+    /// field, and rebuild it from `Default` or the given expression on load.
+    pub skip: Option<Option<Expr>>,
+}
+
+impl FieldAttrs {
+    pub fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut result = FieldAttrs::default();
+        // `default` implies `skip`, but both may be given.
+        let mut skip = false;
+        let mut default = None;
+        for meta in ptrpack_metas(attrs)? {
+            match &meta {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("bits") => {
+                    if result.bits.is_some() {
+                        return Err(Error::new_spanned(nv, "duplicate `bits` attribute"));
+                    }
+                    match &nv.lit {
+                        Lit::Int(lit) => result.bits = Some(lit.base10_parse()?),
+                        lit => return Err(Error::new_spanned(lit, "expected an integer")),
+                    }
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => {
+                    if skip {
+                        return Err(Error::new_spanned(path, "duplicate `skip` attribute"));
+                    }
+                    skip = true;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("default") => {
+                    if default.is_some() {
+                        return Err(Error::new_spanned(nv, "duplicate `default` attribute"));
+                    }
+                    match &nv.lit {
+                        Lit::Str(lit) => default = Some(lit.parse()?),
+                        lit => return Err(Error::new_spanned(lit, "expected a string")),
+                    }
+                }
+                _ => return Err(Error::new_spanned(meta, "unknown ptrpack field attribute")),
+            }
+        }
+        if skip || default.is_some() {
+            result.skip = Some(default);
+        }
+        Ok(result)
+    }
+}
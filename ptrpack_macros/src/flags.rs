@@ -0,0 +1,192 @@
+use crate::attr::ContainerAttrs;
+use crate::packable::{do_derive_packable_with, HelperExtras};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Index, Member, Type};
+
+fn is_bool(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.qself.is_none() && path.path.is_ident("bool"),
+        Type::Group(group) => is_bool(&group.elem),
+        Type::Paren(paren) => is_bool(&paren.elem),
+        _ => false,
+    }
+}
+
+pub fn do_derive_flags(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let name = &input.ident;
+    let vis = &input.vis;
+    let krate = ContainerAttrs::parse(&input.attrs)?.krate();
+
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "`PackFlags` is only supported on structs",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "`PackFlags` is unsupported on generic types",
+        ));
+    }
+
+    // Each flag's mask is computed from its position in the layout, which is
+    // mirrored here for single-bit `bool` fields.
+    let mut members = Vec::new();
+    let mut names = Vec::new();
+    let mut masks = Vec::new();
+    let mut bitstart = quote!(_PackStart);
+    for (idx, field) in data.fields.iter().enumerate() {
+        if !is_bool(&field.ty) || has_ptrpack_attr(field) {
+            return Err(Error::new_spanned(
+                &field.ty,
+                "`PackFlags` fields must be plain `bool`s",
+            ));
+        }
+        let (member, name) = match &field.ident {
+            Some(ident) => (Member::Named(ident.clone()), ident.to_string()),
+            None => (Member::Unnamed(Index::from(idx)), idx.to_string()),
+        };
+        masks.push(quote!(#krate::RawPackedBits::<#bitstart, bool>::MASK));
+        bitstart = quote!(#krate::bitstart::NextStart<#bitstart, bool>);
+        members.push(member);
+        names.push(name);
+    }
+
+    let construct = |value: bool| match &data.fields {
+        Fields::Named(_) | Fields::Unnamed(_) => quote!(#name { #(#members: #value,)* }),
+        Fields::Unit => quote!(#name),
+    };
+    let (none, all) = (construct(false), construct(true));
+    let count = names.len();
+
+    let helper_impls = quote! {
+        fn flag_bits(value: #name) -> usize {
+            let mut bits = 0usize;
+            unsafe {
+                <#name as #krate::Packable<_PackStart>>::store(
+                    value,
+                    #krate::RawPackedBits::for_bits_mut(&mut bits),
+                );
+            }
+            bits
+        }
+
+        fn from_flag_bits(bits: usize) -> #name {
+            unsafe {
+                <#name as #krate::Packable<_PackStart>>::load(#krate::RawPackedBits::for_bits(&bits))
+            }
+        }
+
+        fn read_flag_bits(&self) -> usize {
+            self.inner.as_raw().read_unshifted_bits()
+        }
+
+        fn write_flag_bits(&mut self, bits: usize) {
+            // Every operation only combines bits within the mask.
+            unsafe { self.inner.as_raw_mut().write_unshifted_bits(bits) }
+        }
+
+        /// Whether no flags are set.
+        #vis fn is_empty(&self) -> bool {
+            self.read_flag_bits() == 0
+        }
+
+        /// Whether every flag is set.
+        #vis fn is_all(&self) -> bool {
+            self.read_flag_bits() == #krate::RawPackedBits::<_PackStart, #name>::MASK
+        }
+
+        /// Whether every flag set in `other` is also set.
+        #vis fn contains(&self, other: #name) -> bool {
+            let other = Self::flag_bits(other);
+            self.read_flag_bits() & other == other
+        }
+
+        /// Whether any flag set in `other` is also set.
+        #vis fn intersects(&self, other: #name) -> bool {
+            self.read_flag_bits() & Self::flag_bits(other) != 0
+        }
+
+        /// The flags set in either `self` or `other`.
+        #vis fn union(&self, other: #name) -> #name {
+            Self::from_flag_bits(self.read_flag_bits() | Self::flag_bits(other))
+        }
+
+        /// The flags set in both `self` and `other`.
+        #vis fn intersection(&self, other: #name) -> #name {
+            Self::from_flag_bits(self.read_flag_bits() & Self::flag_bits(other))
+        }
+
+        /// The flags set in `self` but not in `other`.
+        #vis fn difference(&self, other: #name) -> #name {
+            Self::from_flag_bits(self.read_flag_bits() & !Self::flag_bits(other))
+        }
+
+        /// Set every flag which is set in `other`.
+        #vis fn insert(&mut self, other: #name) {
+            self.write_flag_bits(self.read_flag_bits() | Self::flag_bits(other))
+        }
+
+        /// Clear every flag which is set in `other`.
+        #vis fn remove(&mut self, other: #name) {
+            self.write_flag_bits(self.read_flag_bits() & !Self::flag_bits(other))
+        }
+
+        /// Flip every flag which is set in `other`.
+        #vis fn toggle(&mut self, other: #name) {
+            self.write_flag_bits(self.read_flag_bits() ^ Self::flag_bits(other))
+        }
+
+        /// Iterate over the names of the flags which are set.
+        #vis fn iter(&self) -> impl ::core::iter::Iterator<Item = &'static str> {
+            let bits = self.read_flag_bits();
+            // Spell out the type, so that it can be inferred without any flags.
+            let flags: [(&'static str, usize); #count] = [#((#names, #masks)),*];
+            ::core::iter::IntoIterator::into_iter(flags)
+                .filter(move |&(_, mask)| bits & mask != 0)
+                .map(|(name, _)| name)
+        }
+    };
+
+    let debug_impl = quote! {
+        let mut empty = true;
+        for name in self.iter() {
+            if !empty {
+                f.write_str(" | ")?;
+            }
+            f.write_str(name)?;
+            empty = false;
+        }
+        if empty {
+            f.write_str("(empty)")?;
+        }
+        ::core::result::Result::Ok(())
+    };
+
+    let mut result = do_derive_packable_with(
+        input,
+        HelperExtras {
+            helper_impls,
+            debug_impl: Some(debug_impl),
+        },
+    )?;
+    result.extend(quote! {
+        impl #name {
+            /// No flags set.
+            #vis const NONE: #name = #none;
+
+            /// Every flag set.
+            #vis const ALL: #name = #all;
+        }
+    });
+    Ok(result)
+}
+
+fn has_ptrpack_attr(field: &syn::Field) -> bool {
+    field.attrs.iter().any(|attr| attr.path.is_ident("ptrpack"))
+}
@@ -4,9 +4,11 @@ extern crate proc_macro;
 
 use syn::{parse_macro_input, DeriveInput};
 
+mod attr;
+mod flags;
 mod packable;
 
-#[proc_macro_derive(Packable)]
+#[proc_macro_derive(Packable, attributes(ptrpack))]
 pub fn derive_packable(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -38,5 +40,16 @@ pub fn derive_packable(input: proc_macro::TokenStream) -> proc_macro::TokenStrea
     stream.into()
 }
 
+#[proc_macro_derive(PackFlags, attributes(ptrpack))]
+pub fn derive_pack_flags(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let stream = match flags::do_derive_flags(&input) {
+        Ok(expanded) => expanded,
+        Err(error) => error.to_compile_error(),
+    };
+    stream.into()
+}
+
 #[cfg(test)]
 mod test;
@@ -1,252 +1,1724 @@
-use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
-use syn::fold::Fold;
+use crate::attr::{ContainerAttrs, FieldAttrs, TagPlacement};
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use std::cmp;
+use syn::fold::{self, Fold};
 use syn::spanned::Spanned;
-use syn::{parse_quote, Data, DataEnum, DataStruct, DeriveInput, Error, Fields, Ident, Lifetime};
-
-/// The width of a pointer on the platform which is running this proc-macro.
-const HOST_PTR_WIDTH: u32 = 0usize.leading_zeros();
+use syn::{
+    parse_quote, BoundLifetimes, Data, DataEnum, DataStruct, DeriveInput, Error, Expr, ExprLit,
+    ExprUnary, Field, Fields, GenericParam, Generics, Ident, Lifetime, Lit, Path, TraitBound, Type,
+    TypeBareFn, UnOp, Visibility, WherePredicate,
+};
 
 struct Impls {
+    helper_items: TokenStream,
     helper_impls: TokenStream,
     store_impl: TokenStream,
     load_impl: TokenStream,
+    fits_impl: TokenStream,
+    /// Assertions checked when computing `WIDTH`.
+    width_check: TokenStream,
     next_bitstart: TokenStream,
+    /// Packed views of every stored field, which the helper's `PartialEq`,
+    /// `Eq`, `Hash`, `Debug` and `Visitable` impls forward to.
+    view_tys: Vec<TokenStream>,
+    eq_impl: TokenStream,
+    hash_impl: TokenStream,
+    debug_impl: TokenStream,
+    visit_impl: TokenStream,
 }
 
-fn struct_data(name: &Ident, data: &DataStruct) -> Result<Impls, Error> {
-    let mut helper_impls = TokenStream::new();
+// Unfortunately, using the full types for members such as `&'a T` when
+// computing the `WIDTH` constant produces a compiler error, where the
+// compiler complains it cannot ensure `T` outlives `&'a`. This constraint
+// should be enforced already, as the type exists as a field in the struct
+// we're implementing, but the compiler appears to be unaware.
+//
+// This `fold` pass patches up types used in constants to use inferred
+// lifetimes, which dodges this well-formedness issue. Lifetimes bound by
+// `for<'a> ...` are left alone, as they aren't parameters of the struct.
+#[derive(Default)]
+struct InferLifetimes {
+    bound: Vec<Lifetime>,
+}
+
+impl InferLifetimes {
+    fn with_bound<T>(
+        &mut self,
+        lifetimes: Option<&BoundLifetimes>,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let len = self.bound.len();
+        if let Some(lifetimes) = lifetimes {
+            self.bound
+                .extend(lifetimes.lifetimes.iter().map(|def| def.lifetime.clone()));
+        }
+        let result = f(self);
+        self.bound.truncate(len);
+        result
+    }
+}
+
+impl Fold for InferLifetimes {
+    fn fold_lifetime(&mut self, l: Lifetime) -> Lifetime {
+        if &l.ident == "static" || self.bound.contains(&l) {
+            return l;
+        }
+        Lifetime::new("'_", l.apostrophe)
+    }
+
+    fn fold_bound_lifetimes(&mut self, lifetimes: BoundLifetimes) -> BoundLifetimes {
+        lifetimes
+    }
+
+    fn fold_type_bare_fn(&mut self, ty: TypeBareFn) -> TypeBareFn {
+        let lifetimes = ty.lifetimes.clone();
+        self.with_bound(lifetimes.as_ref(), |this| fold::fold_type_bare_fn(this, ty))
+    }
+
+    fn fold_trait_bound(&mut self, bound: TraitBound) -> TraitBound {
+        let lifetimes = bound.lifetimes.clone();
+        self.with_bound(lifetimes.as_ref(), |this| {
+            fold::fold_trait_bound(this, bound)
+        })
+    }
+}
+
+fn infer_lifetimes(ty: &TokenStream) -> Type {
+    InferLifetimes::default().fold_type(parse_quote!(#ty))
+}
+
+/// A single field which has been assigned a position within a layout.
+struct LaidOutField<'a> {
+    field: &'a Field,
+    fname_s: String,
+    /// The type actually stored in the pack, which may differ from the
+    /// field's declared type.
+    ty: TokenStream,
+    /// Whether `ty` is a `Narrow` wrapping the field's declared type.
+    narrowed: bool,
+    bitstart: TokenStream,
+}
+
+/// Fields of a struct or enum variant, laid out top-down in declaration order.
+struct FieldsLayout<'a> {
+    fields: Vec<LaidOutField<'a>>,
+    /// Bindings for each field, usable both as a pattern and as a constructor
+    /// after the struct or variant path.
+    bindings: TokenStream,
+    /// Like `bindings`, but only including fields which are stored in the
+    /// pack.
+    stored_bindings: TokenStream,
+    /// `FieldLayout` entries for every field, including skipped ones.
+    layout_table: TokenStream,
+    store_impl: TokenStream,
+    load_impl: TokenStream,
+    /// Expression checking that every field fits, with each field bound by
+    /// reference.
+    fits_impl: TokenStream,
+    next_bitstart: TokenStream,
+}
+
+fn fields_layout<'a>(
+    krate: &Path,
+    fields: &'a Fields,
+    start: TokenStream,
+) -> Result<FieldsLayout<'a>, Error> {
+    let mut laid_out = Vec::new();
     let mut store_impl = TokenStream::new();
-    let mut dtor_body = TokenStream::new();
     let mut load_impl = TokenStream::new();
-    let mut ctor_body = TokenStream::new();
-    let mut next_bitstart = quote!(_PackStart);
-    for (idx, field) in data.fields.iter().enumerate() {
-        let ty = &field.ty;
-        let vis = &field.vis;
+    let mut fits_impl = quote!(true);
+    let mut bindings = TokenStream::new();
+    let mut stored_bindings = TokenStream::new();
+    let mut layout_table = TokenStream::new();
+    let mut next_bitstart = start;
+    for (idx, field) in fields.iter().enumerate() {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
 
         let fname_s = match &field.ident {
             Some(name) => name.to_string(),
             None => idx.to_string(),
         };
         let varname = format_ident!("_field_{}", fname_s, span = field.span());
+        let binding = match &field.ident {
+            Some(name) => quote!(#name: #varname,),
+            None => quote!(#varname,),
+        };
+        bindings.extend(binding.clone());
+
+        // Skipped fields take up no space, and are dropped when storing.
+        if let Some(default) = attrs.skip {
+            if attrs.bits.is_some() {
+                return Err(Error::new_spanned(
+                    field,
+                    "skipped fields can't specify `bits`",
+                ));
+            }
+            let default = match default {
+                Some(expr) => quote!(#expr),
+                None => quote!(::core::default::Default::default()),
+            };
+            let start = infer_lifetimes(&next_bitstart);
+            store_impl.extend(quote! {
+                let _ = #varname;
+            });
+            load_impl.extend(quote! {
+                let #varname = #default;
+            });
+            layout_table.extend(quote! {
+                #krate::layout::FieldLayout {
+                    name: #fname_s,
+                    start: <#start as #krate::bitstart::BitStart>::START,
+                    width: 0,
+                },
+            });
+            continue;
+        }
+        stored_bindings.extend(binding);
 
+        // Narrowed integer fields are stored as `Narrow<T, N>`, and are
+        // converted to and from it when storing and loading.
         let bitstart = next_bitstart.clone();
-        next_bitstart = quote!(ptrpack::bitstart::NextStart<#bitstart, #ty>);
+        let field_ty = &field.ty;
+        let (ty, narrow, widen, fits) = match attrs.bits {
+            Some(bits) => {
+                let ty = quote!(#krate::impls::Narrow<#field_ty, #bits>);
+                (
+                    ty.clone(),
+                    quote!(<#ty>::new_unchecked(#varname)),
+                    quote!(.get()),
+                    quote!(<#ty>::new(*#varname).is_some()),
+                )
+            }
+            None => (
+                quote!(#field_ty),
+                quote!(#varname),
+                quote!(),
+                quote!(<#field_ty as #krate::Packable<#bitstart>>::fits(#varname)),
+            ),
+        };
+
+        next_bitstart = quote!(#krate::bitstart::NextStart<#bitstart, #ty>);
 
         // Store Impl
         store_impl.extend(quote! {
-            _pack.write_field::<#bitstart, #ty>(#varname);
-        });
-        dtor_body.extend(match &field.ident {
-            Some(name) => quote!(#name: #varname,),
-            None => quote!(#varname,),
+            _pack.write_field::<#bitstart, #ty>(#narrow);
         });
 
         // Load Impl
         load_impl.extend(quote! {
-            let #varname = _pack.read_field::<#bitstart, #ty>();
+            let #varname = _pack.read_field::<#bitstart, #ty>()#widen;
         });
-        ctor_body.extend(match &field.ident {
-            Some(name) => quote!(#name: #varname,),
-            None => quote!(#varname,),
+
+        // Fits Impl
+        fits_impl.extend(quote!(&& #fits));
+
+        // Layout Table
+        let (start, inferred_ty) = (infer_lifetimes(&bitstart), infer_lifetimes(&ty));
+        layout_table.extend(quote! {
+            #krate::layout::FieldLayout {
+                name: #fname_s,
+                start: <#start as #krate::bitstart::BitStart>::START,
+                width: <#inferred_ty as #krate::Packable<#start>>::WIDTH,
+            },
         });
 
-        // Helper Getter Methods
-        let get_field = format_ident!("get_{}", fname_s, span = field.span());
-        let get_field_mut = format_ident!("set_{}", fname_s, span = field.span());
+        laid_out.push(LaidOutField {
+            field,
+            fname_s,
+            ty,
+            narrowed: attrs.bits.is_some(),
+            bitstart,
+        });
+    }
+
+    let (bindings, stored_bindings) = match fields {
+        Fields::Named(_) => (quote!({ #bindings }), quote!({ #stored_bindings })),
+        Fields::Unnamed(_) => (quote!((#bindings)), quote!((#stored_bindings))),
+        Fields::Unit => (quote!(), quote!()),
+    };
+
+    Ok(FieldsLayout {
+        fields: laid_out,
+        bindings,
+        stored_bindings,
+        layout_table,
+        store_impl,
+        load_impl,
+        fits_impl,
+        next_bitstart,
+    })
+}
+
+/// Forwarding implementations of `PartialEq`, `Hash`, `Debug` and
+/// `Visitable` for the stored fields of a struct or variant, using the packed
+/// view of each field rather than loading it.
+struct FieldViews {
+    tys: Vec<TokenStream>,
+    /// Expression comparing the fields of `self` and `other`.
+    eq: TokenStream,
+    /// Statements hashing the fields of `self` into `state`.
+    hash: TokenStream,
+    /// Expression formatting the fields of `self` into `f`.
+    debug: TokenStream,
+    /// Statements reporting the fields of `self` to `visitor`.
+    visit: TokenStream,
+}
+
+fn field_views(krate: &Path, fields: &Fields, laid_out: &[LaidOutField], name: &str) -> FieldViews {
+    let mut tys = Vec::new();
+    let mut eq = quote!(true);
+    let mut hash = TokenStream::new();
+    let mut debug_fields = TokenStream::new();
+    let mut visit = TokenStream::new();
+    for LaidOutField {
+        fname_s,
+        ty,
+        bitstart,
+        ..
+    } in laid_out
+    {
+        let view = |recv| quote!(unsafe { #recv.inner.as_field::<#bitstart, #ty>().as_packed() });
+        let (this, other) = (view(quote!(self)), view(quote!(other)));
+        eq.extend(quote!(&& ::core::cmp::PartialEq::eq(#this, #other)));
+        hash.extend(quote!(::core::hash::Hash::hash(#this, state);));
+        visit.extend(quote!(visitor.visit_field(#fname_s, #this);));
+        debug_fields.extend(match fields {
+            Fields::Named(_) => quote!(.field(#fname_s, #this)),
+            _ => quote!(.field(#this)),
+        });
+        tys.push(quote!(<#ty as #krate::Packable<#bitstart>>::Packed));
+    }
+    let debug = match fields {
+        Fields::Named(_) => quote!(f.debug_struct(#name)#debug_fields.finish()),
+        Fields::Unnamed(_) => quote!(f.debug_tuple(#name)#debug_fields.finish()),
+        Fields::Unit => quote!(f.write_str(#name)),
+    };
+    FieldViews {
+        tys,
+        eq,
+        hash,
+        debug,
+        visit,
+    }
+}
+
+fn struct_data(
+    name: &Ident,
+    vis: &Visibility,
+    generics: &Generics,
+    attrs: &ContainerAttrs,
+    data: &DataStruct,
+) -> Result<Impls, Error> {
+    let krate = &attrs.krate();
+    let FieldsLayout {
+        fields,
+        bindings,
+        store_impl,
+        load_impl,
+        next_bitstart,
+        fits_impl,
+        layout_table,
+        ..
+    } = fields_layout(krate, &data.fields, quote!(_PackStart))?;
+    let views = field_views(krate, &data.fields, &fields, &name.to_string());
+
+    let mut helper_impls = quote! {
+        /// The position of each field within the packed value.
+        pub const LAYOUT: &'static [#krate::layout::FieldLayout] = &[#layout_table];
+    };
+    for LaidOutField {
+        field,
+        fname_s,
+        ty,
+        narrowed,
+        bitstart,
+    } in &fields
+    {
+        let vis = &field.vis;
+        let field_ty = &field.ty;
+        let packed_ty = quote!(<#ty as #krate::Packable<#bitstart>>::Packed);
+
+        // Narrowed fields are stored as `Narrow<T, N>`, but the accessors
+        // speak in terms of the declared type.
+        let (narrow, widen) = if *narrowed {
+            (quote!(<#ty>::new_unchecked(value)), quote!(.get()))
+        } else {
+            (quote!(value), quote!())
+        };
+
+        // Tuple struct fields are prefixed to form a valid identifier.
+        let getter = match &field.ident {
+            Some(fname) => fname.clone(),
+            None => format_ident!("_{}", fname_s, span = field.span()),
+        };
+        let getter_mut = format_ident!("{}_mut", getter);
+        let setter = format_ident!("set_{}", fname_s, span = field.span());
+        let replacer = format_ident!("replace_{}", fname_s, span = field.span());
+        let old_getter = format_ident!("get_{}", fname_s, span = field.span());
+
+        // The `for<'__a>` hides the trivial bound from rustc, which would
+        // otherwise reject it for non-`Copy` field types.
         helper_impls.extend(quote! {
+            #vis fn #getter(&self) -> #field_ty
+            where
+                for<'__a> #ty: ::core::marker::Copy,
+            {
+                unsafe { self.inner.as_field::<#bitstart, #ty>().get()#widen }
+            }
+
             // It'd be lovely if I could use associated types here - these decls
             // can end up really long!
-            #vis fn #get_field(&self) -> &<#ty as ptrpack::Packable<#bitstart>>::Packed {
+            #vis fn #old_getter(&self) -> &#packed_ty {
                 unsafe { self.inner.as_field::<#bitstart, #ty>().as_packed() }
             }
 
-            #vis fn #get_field_mut(&mut self) -> &mut <#ty as ptrpack::Packable<#bitstart>>::Packed {
+            #vis fn #getter_mut(&mut self) -> &mut #packed_ty {
                 unsafe { self.inner.as_field_mut::<#bitstart, #ty>().as_packed_mut() }
             }
+
+            #vis fn #replacer(&mut self, value: #field_ty) -> #field_ty {
+                unsafe {
+                    self.inner
+                        .as_field_mut::<#bitstart, #ty>()
+                        .replace(#narrow)#widen
+                }
+            }
+        });
+
+        // `set_` takes the new value, unless the old form returning a mutable
+        // reference is kept with `#[ptrpack(legacy_setters)]`.
+        helper_impls.extend(if attrs.legacy_setters.is_some() {
+            quote! {
+                #[deprecated(note = "use the `_mut` accessor instead")]
+                #vis fn #setter(&mut self) -> &mut #packed_ty {
+                    self.#getter_mut()
+                }
+            }
+        } else {
+            quote! {
+                #vis fn #setter(&mut self, value: #field_ty) {
+                    unsafe { self.inner.as_field_mut::<#bitstart, #ty>().set(#narrow) }
+                }
+            }
         });
     }
 
-    let destruct = match &data.fields {
-        Fields::Named(_) => quote!(#name { #dtor_body }),
-        Fields::Unnamed(_) => quote!(#name(#dtor_body)),
-        Fields::Unit => quote!(#name),
-    };
+    let mut helper_items = builder_items(krate, name, vis, generics, data)?;
+    if let Some(repr) = &attrs.repr {
+        helper_items.extend(repr_items(krate, name, vis, repr, data)?);
+    }
+
     let store_impl = quote! {
-        let #destruct = self;
+        let #name #bindings = self;
         #store_impl
     };
 
     // Load Impl Ctor
-    load_impl.extend(match &data.fields {
-        Fields::Named(_) => quote!(#name { #ctor_body }),
-        Fields::Unnamed(_) => quote!(#name(#ctor_body)),
-        Fields::Unit => quote!(#name),
-    });
+    let load_impl = quote! {
+        #load_impl
+        #name #bindings
+    };
+
+    let fits_impl = quote! {
+        let #name #bindings = self;
+        #fits_impl
+    };
 
     Ok(Impls {
         load_impl,
         store_impl,
+        fits_impl,
+        width_check: TokenStream::new(),
+        helper_items,
         helper_impls,
         next_bitstart,
+        view_tys: views.tys,
+        eq_impl: views.eq,
+        hash_impl: views.hash,
+        debug_impl: views.debug,
+        visit_impl: views.visit,
     })
 }
 
-fn enum_data(name: &Ident, data: &DataEnum) -> Result<Impls, Error> {
-    // FIXME: Should there be a behaviour for packing values with non-trivial
-    // enum variants (either unit or single-element tuple variants?).
-    //
-    // Doing so is certainly possible, but the API might not be super great.
+/// Generate the `Packed*With` trait, which adds `with_` methods to top-level
+/// packs, and the `Packed*Builder` type.
+fn builder_items(
+    krate: &Path,
+    name: &Ident,
+    vis: &Visibility,
+    generics: &Generics,
+    data: &DataStruct,
+) -> Result<TokenStream, Error> {
+    // Builders always write to a whole `Pack`, so lay the fields out again
+    // starting at `DefaultStart`.
+    let default_start = quote!(#krate::bitstart::DefaultStart);
+    let FieldsLayout { fields, .. } = fields_layout(krate, &data.fields, default_start.clone())?;
 
-    // FIXME: Extra code can probably be generated for the `Option<T>`-style
-    // case to support the nonzero pointer optimization. Perhaps detect the
-    // value looks like `Option<T>`, and forward to it under the hood?
+    let mut base_generics = generics.clone();
+    base_generics.params = generics
+        .params
+        .iter()
+        .filter(|param| !matches!(param, GenericParam::Type(ty) if ty.ident == "_PackStart"))
+        .cloned()
+        .collect();
+    let (impl_generics, type_generics, where_clause) = base_generics.split_for_impl();
 
-    // How many bits are required for the discriminant.
-    let discr_bits = HOST_PTR_WIDTH - (data.variants.len() - 1).leading_zeros();
-    if discr_bits > 32 {
-        return Err(Error::new_spanned(
-            data.enum_token,
-            "Too many variants! At most 2^32 - 1 variants are supported",
+    let with_name = format_ident!("Packed{}With", name);
+    let builder_name = format_ident!("Packed{}Builder", name);
+    let target_ty = quote!(#name #type_generics);
+    let field_count = fields.len();
+
+    let mut with_decls = TokenStream::new();
+    let mut with_methods = TokenStream::new();
+    let mut builder_methods = TokenStream::new();
+    let mut build_checks = TokenStream::new();
+    let mut drop_fields = TokenStream::new();
+    for (idx, field) in fields.iter().enumerate() {
+        let LaidOutField {
+            field,
+            fname_s,
+            ty,
+            narrowed,
+            bitstart,
+        } = field;
+        let vis = &field.vis;
+        let field_ty = &field.ty;
+        let narrow = if *narrowed {
+            quote!(<#ty>::new_unchecked(value))
+        } else {
+            quote!(value)
+        };
+
+        let setter = match &field.ident {
+            Some(fname) => fname.clone(),
+            None => format_ident!("_{}", fname_s, span = field.span()),
+        };
+        let with = format_ident!("with_{}", fname_s, span = field.span());
+        let replacer = format_ident!("replace_{}", fname_s, span = field.span());
+
+        with_decls.extend(quote! {
+            fn #with(self, value: #field_ty) -> #krate::Pack<#target_ty>;
+        });
+        with_methods.extend(quote! {
+            fn #with(mut self, value: #field_ty) -> #krate::Pack<#target_ty> {
+                self.#replacer(value);
+                self
+            }
+        });
+
+        // Values which are overwritten are dropped, like the rest of the
+        // written fields when the builder is dropped without being built.
+        builder_methods.extend(quote! {
+            #vis fn #setter(mut self, value: #field_ty) -> Self {
+                unsafe {
+                    let raw = #krate::RawPackedBits::<#default_start, #target_ty>::for_bits_mut(
+                        &mut self.bits,
+                    );
+                    if self.set[#idx] {
+                        ::core::mem::drop(raw.read_field::<#bitstart, #ty>());
+                    }
+                    raw.write_field::<#bitstart, #ty>(#narrow);
+                }
+                self.set[#idx] = true;
+                self
+            }
+        });
+        drop_fields.extend(quote! {
+            if self.set[#idx] {
+                ::core::mem::drop(raw.read_field::<#bitstart, #ty>());
+            }
+        });
+
+        let message = format!("field `{}` was not set", fname_s);
+        build_checks.extend(quote! {
+            ::core::assert!(self.set[#idx], #message);
+        });
+    }
+
+    // The `with_` methods live on a trait, as they're implemented for
+    // `Pack`, so point readers of both generated types at the import.
+    let with_doc = format!(
+        "This trait must be imported to call the `with_` methods on \
+         `Pack<{}>`.",
+        name
+    );
+    let builder_doc = format!(
+        "To replace a field of an existing `Pack<{}>` instead, import \
+         `{}`.",
+        name, with_name
+    );
+
+    Ok(quote! {
+        /// Functional updates of a packed value, returning a new pack with one
+        /// field replaced.
+        ///
+        #[doc = #with_doc]
+        #vis trait #with_name #impl_generics #where_clause {
+            #with_decls
+        }
+
+        impl #impl_generics #with_name #type_generics for #krate::Pack<#target_ty> #where_clause {
+            #with_methods
+        }
+
+        /// Builds a packed value by writing each field directly into the
+        /// packed bits.
+        ///
+        /// Every stored field must be set before calling `build`, which panics
+        /// otherwise.
+        ///
+        #[doc = #builder_doc]
+        #vis struct #builder_name #base_generics #where_clause {
+            bits: usize,
+            set: [bool; #field_count],
+            _marker: ::core::marker::PhantomData<#target_ty>,
+        }
+
+        impl #impl_generics #builder_name #type_generics #where_clause {
+            #vis fn new() -> Self {
+                #builder_name {
+                    bits: 0,
+                    set: [false; #field_count],
+                    _marker: ::core::marker::PhantomData,
+                }
+            }
+
+            #builder_methods
+
+            /// Finish building the packed value.
+            ///
+            /// # Panics
+            ///
+            /// Panics if any stored field has not been set, naming the first
+            /// such field.
+            #vis fn build(self) -> #krate::Pack<#target_ty> {
+                #build_checks
+                let this = ::core::mem::ManuallyDrop::new(self);
+                unsafe { #krate::Pack::from_bits(this.bits) }
+            }
+        }
+
+        impl #impl_generics ::core::ops::Drop for #builder_name #type_generics #where_clause {
+            fn drop(&mut self) {
+                unsafe {
+                    let raw = #krate::RawPackedBits::<#default_start, #target_ty>::for_bits(
+                        &self.bits,
+                    );
+                    #drop_fields
+                }
+            }
+        }
+
+        impl #impl_generics ::core::default::Default for #builder_name #type_generics #where_clause {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    })
+}
+
+/// Generate the `Packed*Repr` bitfield type for `#[ptrpack(repr = "...")]`,
+/// which stores the struct in the low bits of the integer type `repr`. Every
+/// method besides the `set_` methods is a `const fn`.
+fn repr_items(
+    krate: &Path,
+    name: &Ident,
+    vis: &Visibility,
+    repr: &Ident,
+    data: &DataStruct,
+) -> Result<TokenStream, Error> {
+    let repr_start = quote!(#krate::bitstart::ReprStart<{ ::core::primitive::#repr::BITS }>);
+    let FieldsLayout {
+        fields, bindings, ..
+    } = fields_layout(krate, &data.fields, repr_start.clone())?;
+
+    let repr_name = format_ident!("Packed{}Repr", name);
+
+    // Fields are converted using `read_plain` and `write_plain`, rather than
+    // `Packable`, so that the helpers can be `const`.
+    let mut plain_checks = TokenStream::new();
+    let mut accessors = TokenStream::new();
+    let mut stores = TokenStream::new();
+    let mut loads = TokenStream::new();
+    for LaidOutField {
+        field,
+        fname_s,
+        ty,
+        narrowed,
+        bitstart,
+    } in &fields
+    {
+        let vis = &field.vis;
+        let field_ty = &field.ty;
+        let varname = format_ident!("_field_{}", fname_s, span = field.span());
+        let narrow = |value| {
+            if *narrowed {
+                quote!(<#ty>::new_unchecked(#value))
+            } else {
+                quote!(#value)
+            }
+        };
+        let widen = if *narrowed { quote!(.get()) } else { quote!() };
+        let read = quote!(#krate::__support::read_plain::<#bitstart, #ty>(bits)#widen);
+        let write = |value| {
+            let value = narrow(value);
+            quote!(#krate::__support::write_plain::<#bitstart, #ty>(bits, #value))
+        };
+        let (write_value, write_field) = (write(quote!(value)), write(quote!(#varname)));
+
+        let getter = match &field.ident {
+            Some(fname) => fname.clone(),
+            None => format_ident!("_{}", fname_s, span = field.span()),
+        };
+        let setter = format_ident!("set_{}", fname_s, span = field.span());
+        let with = format_ident!("with_{}", fname_s, span = field.span());
+
+        plain_checks.extend(quote! {
+            assert_plain::<#bitstart, #ty>();
+        });
+        stores.extend(quote! {
+            let bits = unsafe { #write_field };
+        });
+        loads.extend(quote! {
+            let #varname = unsafe { #read };
+        });
+        accessors.extend(quote! {
+            #vis const fn #getter(self) -> #field_ty {
+                let bits = self.0 as usize;
+                unsafe { #read }
+            }
+
+            #vis fn #setter(&mut self, value: #field_ty) {
+                *self = self.#with(value);
+            }
+
+            #vis const fn #with(self, value: #field_ty) -> Self {
+                let bits = self.0 as usize;
+                #repr_name(unsafe { #write_value } as #repr)
+            }
+        });
+    }
+
+    // Skipped fields may not be dropped or created in a `const fn`, so `new`
+    // and `get` are only `const` when every field is stored.
+    let mut constness = quote!(const);
+    for (idx, field) in data.fields.iter().enumerate() {
+        if let Some(default) = FieldAttrs::parse(&field.attrs)?.skip {
+            let fname_s = match &field.ident {
+                Some(name) => name.to_string(),
+                None => idx.to_string(),
+            };
+            let varname = format_ident!("_field_{}", fname_s, span = field.span());
+            let default = match default {
+                Some(expr) => quote!(#expr),
+                None => quote!(::core::default::Default::default()),
+            };
+            stores.extend(quote! {
+                let _ = #varname;
+            });
+            loads.extend(quote! {
+                let #varname = #default;
+            });
+            constness = TokenStream::new();
+        }
+    }
+
+    let message = format!("the fields of `{}` don't fit in `{}`", name, repr);
+    Ok(quote! {
+        const _: () = ::core::assert!(
+            <#name as #krate::Packable<#krate::bitstart::DefaultStart>>::WIDTH
+                <= ::core::primitive::#repr::BITS,
+            #message,
+        );
+
+        // Any bits may be passed to `from_repr`, so every field must be valid
+        // for any bit pattern.
+        #[allow(dead_code)]
+        const _: () = {
+            fn assert_plain<S: #krate::bitstart::BitStart, T: #krate::PlainPackable<S>>() {}
+            fn assert_fields() {
+                #plain_checks
+            }
+        };
+
+        /// Bitfield representation of the struct, stored in the low bits of
+        /// an integer.
+        #[repr(transparent)]
+        #[derive(
+            ::core::marker::Copy,
+            ::core::clone::Clone,
+            ::core::cmp::PartialEq,
+            ::core::cmp::Eq,
+            ::core::hash::Hash,
+            ::core::fmt::Debug,
+        )]
+        #vis struct #repr_name(#repr);
+
+        impl #repr_name {
+            /// Wrap the raw bits of the bitfield.
+            #vis const fn from_repr(repr: #repr) -> Self {
+                #repr_name(repr)
+            }
+
+            /// The raw bits of the bitfield.
+            #vis const fn to_repr(self) -> #repr {
+                self.0
+            }
+
+            #vis #constness fn new(value: #name) -> Self {
+                let #name #bindings = value;
+                let bits = 0usize;
+                #stores
+                #repr_name(bits as #repr)
+            }
+
+            #vis #constness fn get(self) -> #name {
+                let bits = self.0 as usize;
+                #loads
+                #name #bindings
+            }
+
+            #accessors
+        }
+
+        // The `for<'__a>` hides the trivial bound from rustc, which would
+        // otherwise reject it for fields which can't be visited.
+        impl #krate::visit::Visitable for #repr_name
+        where
+            for<'__a> <#name as #krate::Packable<#repr_start>>::Packed: #krate::visit::Visitable,
+        {
+            fn visit(&self, visitor: &mut dyn #krate::visit::PackVisitor) {
+                let bits = self.0 as usize;
+                let packed: &<#name as #krate::Packable<#repr_start>>::Packed =
+                    unsafe { ::core::mem::transmute(&bits) };
+                #krate::visit::Visitable::visit(packed, visitor)
+            }
+        }
+    })
+}
+
+fn transparent_data(
+    krate: &Path,
+    span: Span,
+    name: &Ident,
+    data: &DataStruct,
+) -> Result<Impls, Error> {
+    let FieldsLayout {
+        fields,
+        bindings,
+        store_impl,
+        load_impl,
+        next_bitstart,
+        fits_impl,
+        ..
+    } = fields_layout(krate, &data.fields, quote!(_PackStart))?;
+    if fields.len() != 1 {
+        return Err(Error::new(
+            span,
+            "`transparent` requires exactly one stored field",
         ));
     }
-    let discr_ty_id = format_ident!("U{}", discr_bits);
-    let discr_ty = quote!(ptrpack::impls::#discr_ty_id);
+    let LaidOutField { ty, bitstart, .. } = &fields[0];
 
-    let bitstart = quote!(_PackStart);
-    let mut store_arms = TokenStream::new();
-    let mut load_arms = TokenStream::new();
-    let mut discr_bitstart: Option<TokenStream> = None;
+    Ok(Impls {
+        load_impl: quote! {
+            #load_impl
+            #name #bindings
+        },
+        store_impl: quote! {
+            let #name #bindings = self;
+            #store_impl
+        },
+        fits_impl: quote! {
+            let #name #bindings = self;
+            #fits_impl
+        },
+        width_check: TokenStream::new(),
+        helper_items: TokenStream::new(),
+        helper_impls: TokenStream::new(),
+        next_bitstart,
+        view_tys: vec![quote!(<#ty as #krate::Packable<#bitstart>>::Packed)],
+        eq_impl: TokenStream::new(),
+        hash_impl: TokenStream::new(),
+        debug_impl: TokenStream::new(),
+        visit_impl: quote! {
+            let field = unsafe { packed.as_field::<#bitstart, #ty>().as_packed() };
+            #krate::visit::Visitable::visit(field, visitor)
+        },
+    })
+}
+
+/// Compute the code used to store the discriminant of each variant.
+///
+/// Discriminants must be implicit or integer literals, and are stored
+/// directly, so that the packed bits match the value of an `as` cast.
+/// Negative discriminants are stored in two's complement using the width of
+/// the enum's signed `#[repr]`, such as `#[repr(i8)]`. As the derive doesn't
+/// know the target's pointer width, `#[repr(isize)]` is treated as 64 bits
+/// wide, and so negative `isize` discriminants only fit on 64-bit targets.
+fn discriminant_codes(data: &DataEnum, repr: Option<&Ident>) -> Result<Vec<u128>, Error> {
+    let signed_bits = repr.and_then(|repr| match repr.to_string().as_str() {
+        "i8" => Some(8),
+        "i16" => Some(16),
+        "i32" => Some(32),
+        "i64" | "isize" => Some(64),
+        "i128" => Some(128),
+        _ => None,
+    });
+
+    let mut codes = Vec::with_capacity(data.variants.len());
+    let mut next = Some(0i128);
     for variant in &data.variants {
-        match &variant.fields {
-            Fields::Named(_) => {
-                // FIXME: Better errors
+        let value = match &variant.discriminant {
+            Some((_, expr)) => literal_discriminant(expr)?,
+            None => next.ok_or_else(|| {
+                Error::new_spanned(&variant.ident, "enum discriminant overflowed")
+            })?,
+        };
+        let code = match signed_bits {
+            _ if value >= 0 => value as u128,
+            Some(bits) => value as u128 & (u128::MAX >> (128 - bits)),
+            None => {
                 return Err(Error::new_spanned(
-                    variant,
-                    "struct-style enum variants unsupported",
-                ));
+                    &variant.discriminant.as_ref().unwrap().1,
+                    "negative discriminants require a signed `#[repr]`, such as `#[repr(i8)]`",
+                ))
             }
-            Fields::Unnamed(fields) => {
-                if fields.unnamed.len() != 1 {
-                    // FIXME: Better errors
-                    return Err(Error::new_spanned(
-                        variant,
-                        "multiple fields in enum variants are unsupported",
-                    ));
+        };
+        codes.push(code);
+        next = value.checked_add(1);
+    }
+    Ok(codes)
+}
+
+/// Evaluate a discriminant expression, which must be an integer literal,
+/// possibly negated.
+///
+/// Whether the value fits in a pointer-sized integer depends on the target,
+/// so is checked by the generated code rather than here.
+fn literal_discriminant(expr: &Expr) -> Result<i128, Error> {
+    match expr {
+        Expr::Group(group) => literal_discriminant(&group.expr),
+        Expr::Paren(paren) => literal_discriminant(&paren.expr),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr: inner,
+            ..
+        }) => literal_discriminant(inner)?
+            .checked_neg()
+            .ok_or_else(|| Error::new_spanned(expr, "discriminant is too large")),
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_parse::<i128>(),
+        _ => Err(Error::new_spanned(
+            expr,
+            "explicit discriminants must be integer literals",
+        )),
+    }
+}
+
+/// Convert a `CamelCase` variant name into `snake_case` for use in method
+/// names.
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+    for (idx, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && idx > 0 {
+            let prev = chars[idx - 1];
+            let next_lower = chars.get(idx + 1).is_some_and(|c| c.is_lowercase());
+            if prev != '_' && (!prev.is_uppercase() || next_lower) {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+    result
+}
+
+/// How the discriminant of an enum is stored.
+enum Encoding {
+    /// A fixed-width tag of type `ty`, stored at `bitstart`.
+    Tag {
+        bitstart: TokenStream,
+        ty: TokenStream,
+    },
+    /// Unit variants stored as the first `units` values of the payload of the
+    /// only data-carrying variant, which has type `ty`.
+    Niche { ty: TokenStream, units: usize },
+    /// A variable-length tag stored at `_PackStart`, with the tag of each
+    /// variant given by the `PrefixTags` impl of `ty`.
+    Prefix { ty: TokenStream, variants: usize },
+}
+
+impl Encoding {
+    /// Expression reading the variant's code from the `RawPackedBits` `raw`.
+    fn read(&self, krate: &Path, raw: TokenStream) -> TokenStream {
+        match self {
+            Encoding::Tag { bitstart, ty } => quote! {
+                #raw.read_field::<#bitstart, #ty>().get()
+            },
+            Encoding::Niche { ty, units } => quote! {{
+                let raw = #raw.as_field::<_PackStart, #ty>().read_low_bits();
+                if raw < #units {
+                    raw
+                } else {
+                    #units
                 }
+            }},
+            Encoding::Prefix { ty, variants } => {
+                // Tags form a prefix code, so only one can match.
+                let last = variants.saturating_sub(1);
+                let checks = (0..last).map(|code| {
+                    quote! {
+                        if bits.wrapping_shr(start - tags[#code].width) == tags[#code].code {
+                            #code
+                        } else
+                    }
+                });
+                quote!({
+                    let bits = #raw.read_unshifted_bits();
+                    let tags = <#ty as #krate::bitstart::PrefixTags<_PackStart>>::TAGS;
+                    let start = <_PackStart as #krate::bitstart::BitStart>::START;
+                    #(#checks)* {
+                        #last
+                    }
+                })
+            }
+        }
+    }
 
-                // Update bitstart value for the discriminant.
-                let ty = &fields.unnamed[0].ty;
-                let after_bitstart = quote!(ptrpack::bitstart::NextStart<#bitstart, #ty>);
-                discr_bitstart = discr_bitstart
-                    .map(|bs| quote!(ptrpack::bitstart::UnionStart<#bs, #after_bitstart>))
-                    .or_else(|| Some(after_bitstart.clone()));
+    /// Statement writing the variant's code `discr` to the `RawPackedBits`
+    /// `raw`, after the variant's fields have been written.
+    fn write(&self, krate: &Path, raw: TokenStream) -> TokenStream {
+        match self {
+            Encoding::Tag { bitstart, ty } => quote! {
+                #raw.write_field::<#bitstart, #ty>(<#ty>::new_unchecked(discr));
+            },
+            Encoding::Niche { ty, units } => quote! {
+                if discr < #units {
+                    #raw.as_field_mut::<_PackStart, #ty>().write_low_bits(discr);
+                }
+            },
+            Encoding::Prefix { ty, .. } => quote! {
+                let tag = <#ty as #krate::bitstart::PrefixTags<_PackStart>>::TAGS[discr];
+                let start = <_PackStart as #krate::bitstart::BitStart>::START;
+                // Clear any previous tag before writing the new one.
+                let mask = usize::MAX.checked_shr(usize::BITS - tag.width).unwrap_or(0);
+                let shift = start - tag.width;
+                let bits = #raw.read_unshifted_bits() & !mask.wrapping_shl(shift);
+                #raw.write_unshifted_bits(bits | tag.code.wrapping_shl(shift));
+            },
+        }
+    }
+
+    /// `tag`, `tag_start` and `tag_width` fields of the `VariantLayout` for
+    /// the variant with code `code`.
+    fn layout(&self, krate: &Path, code: u128) -> TokenStream {
+        let tag = Literal::u128_unsuffixed(code);
+        match self {
+            Encoding::Tag { bitstart, ty } => {
+                let bitstart = infer_lifetimes(bitstart);
+                quote! {
+                    tag: #tag,
+                    tag_start: <#bitstart as #krate::bitstart::BitStart>::START,
+                    tag_width: <#ty as #krate::Packable<#bitstart>>::WIDTH,
+                }
+            }
+            Encoding::Niche { ty, units } if code < *units as u128 => {
+                let ty = infer_lifetimes(ty);
+                quote! {
+                    tag: #tag,
+                    tag_start: <_PackStart as #krate::bitstart::BitStart>::START,
+                    tag_width: <#ty as #krate::Packable<_PackStart>>::WIDTH,
+                }
+            }
+            Encoding::Niche { .. } => quote! {
+                tag: 0,
+                tag_start: <_PackStart as #krate::bitstart::BitStart>::START,
+                tag_width: 0,
+            },
+            Encoding::Prefix { ty, .. } => {
+                let ty = infer_lifetimes(ty);
+                let code = code as usize;
+                quote! {
+                    tag: <#ty as #krate::bitstart::PrefixTags<_PackStart>>::TAGS[#code].code,
+                    tag_start: <_PackStart as #krate::bitstart::BitStart>::START,
+                    tag_width: <#ty as #krate::bitstart::PrefixTags<_PackStart>>::TAGS[#code].width,
+                }
             }
-            Fields::Unit => {}
         }
     }
+}
 
-    let discr_bitstart = discr_bitstart.unwrap_or_else(|| bitstart.clone());
-    let next_bitstart = quote!(ptrpack::bitstart::NextStart<#discr_bitstart, #discr_ty>);
+/// Check that an enum is suitable for `#[ptrpack(niche)]`, returning the
+/// index of its data-carrying variant.
+fn niche_variant(span: Span, data: &DataEnum, layouts: &[FieldsLayout]) -> Result<usize, Error> {
+    if let Some((_, expr)) = data.variants.iter().find_map(|v| v.discriminant.as_ref()) {
+        return Err(Error::new_spanned(
+            expr,
+            "explicit discriminants are unsupported with `niche`",
+        ));
+    }
 
-    for (idx, variant) in data.variants.iter().enumerate() {
-        let variant_name = &variant.ident;
+    let mut with_fields = layouts
+        .iter()
+        .enumerate()
+        .filter(|(_, layout)| !layout.fields.is_empty());
+    match (with_fields.next(), with_fields.next()) {
+        (Some((idx, layout)), None) if layout.fields.len() == 1 => Ok(idx),
+        (Some((idx, _)), None) => Err(Error::new_spanned(
+            &data.variants[idx],
+            "`niche` requires the data-carrying variant to have a single field",
+        )),
+        _ => Err(Error::new(
+            span,
+            "`niche` requires exactly one variant with fields",
+        )),
+    }
+}
 
-        match &variant.fields {
-            Fields::Named(_) => unreachable!(),
-            Fields::Unnamed(fields) => {
-                assert!(fields.unnamed.len() == 1);
+fn enum_data(
+    name: &Ident,
+    vis: &Visibility,
+    generics: &Generics,
+    attrs: &ContainerAttrs,
+    data: &DataEnum,
+) -> Result<Impls, Error> {
+    // FIXME: Extra code can probably be generated for the `Option<T>`-style
+    // case to support the nonzero pointer optimization. Perhaps detect the
+    // value looks like `Option<T>`, and forward to it under the hood?
 
-                let ty = &fields.unnamed[0].ty;
-                store_arms.extend(quote! {
-                    #name::#variant_name(_field) => {
-                        _pack.write_field::<#bitstart, #ty>(_field);
-                        #discr_ty::new_unchecked(#idx)
-                    }
-                });
-                load_arms.extend(quote! {
-                    #idx => #name::#variant_name(_pack.read_field::<#bitstart, #ty>()),
-                });
+    let krate = &attrs.krate();
+    if let (Some(span), Some(_)) = (attrs.niche, attrs.tag) {
+        return Err(Error::new(span, "`niche` and `tag` can't be used together"));
+    }
+
+    let mut width_check = TokenStream::new();
+    let mut target_checks = TokenStream::new();
+    let mut tag_items = TokenStream::new();
+    let (codes, layouts, encoding, next_bitstart) = if let Some(span) = attrs.niche {
+        if let Some((_, expr)) = data.variants.iter().find_map(|v| v.discriminant.as_ref()) {
+            return Err(Error::new_spanned(
+                expr,
+                "explicit discriminants are unsupported with `niche`",
+            ));
+        }
+
+        // Unit variants are numbered first, followed by the data-carrying
+        // variant. No additional bits are needed.
+        let layouts: Vec<_> = data
+            .variants
+            .iter()
+            .map(|variant| fields_layout(krate, &variant.fields, quote!(_PackStart)))
+            .collect::<Result<_, _>>()?;
+        let data_idx = niche_variant(span, data, &layouts)?;
+        let units = data.variants.len() - 1;
+        let codes = (0..data.variants.len() as u128)
+            .map(|idx| match idx.cmp(&(data_idx as u128)) {
+                cmp::Ordering::Less => idx,
+                cmp::Ordering::Equal => units as u128,
+                cmp::Ordering::Greater => idx - 1,
+            })
+            .collect();
+
+        let ty = layouts[data_idx].fields[0].ty.clone();
+        let next_bitstart = layouts[data_idx].next_bitstart.clone();
+        let inferred_ty = infer_lifetimes(&ty);
+        width_check.extend(quote! {
+            ::core::assert!(
+                #units <= <#inferred_ty as #krate::NichePackable<_PackStart>>::NICHES,
+                "too many unit variants to store in niche values",
+            );
+        });
+        (codes, layouts, Encoding::Niche { ty, units }, next_bitstart)
+    } else if let Some((TagPlacement::Prefix, _)) = attrs.tag {
+        if let Some((_, expr)) = data.variants.iter().find_map(|v| v.discriminant.as_ref()) {
+            return Err(Error::new_spanned(
+                expr,
+                "explicit discriminants are unsupported with `tag = \"prefix\"`",
+            ));
+        }
+
+        // Tags are assigned from the width of each variant's payload, so that
+        // narrower variants get longer tags. Each payload starts directly
+        // after its own tag.
+        let variants = data.variants.len();
+        let mut base_generics = generics.clone();
+        base_generics.params.pop();
+        let (_, base_type_generics, _) = base_generics.split_for_impl();
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let target_ty = quote!(#name #base_type_generics);
+
+        let mut payload_widths = Vec::new();
+        let mut widest_payload = quote!(_PackStart);
+        for variant in &data.variants {
+            let probe = fields_layout(krate, &variant.fields, quote!(_PackStart))?;
+            let after_bitstart = infer_lifetimes(&probe.next_bitstart);
+            payload_widths.push(quote! {
+                <_PackStart as #krate::bitstart::BitStart>::START
+                    - <#after_bitstart as #krate::bitstart::BitStart>::START
+            });
+            widest_payload = quote!(#krate::bitstart::UnionStart<#widest_payload, #after_bitstart>);
+        }
+        tag_items.extend(quote! {
+            impl #impl_generics #krate::bitstart::PrefixTags<_PackStart> for #target_ty #where_clause {
+                const TAGS: &'static [#krate::bitstart::PrefixTag] =
+                    &#krate::__support::prefix_tags([#(#payload_widths),*]);
             }
-            Fields::Unit => {
-                store_arms.extend(quote! {
-                    #name::#variant_name => #idx,
-                });
-                load_arms.extend(quote! {
-                    #idx => #name::#variant_name,
+        });
+
+        let layouts: Vec<_> = data
+            .variants
+            .iter()
+            .enumerate()
+            .map(|(idx, variant)| {
+                let start = quote!(#krate::bitstart::PrefixStart<_PackStart, #target_ty, #idx>);
+                fields_layout(krate, &variant.fields, start)
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut next_bitstart = quote!(_PackStart);
+        for layout in &layouts {
+            let after_bitstart = &layout.next_bitstart;
+            next_bitstart = quote!(#krate::bitstart::UnionStart<#next_bitstart, #after_bitstart>);
+        }
+
+        let inferred_next = infer_lifetimes(&next_bitstart);
+        let fixed_width = usize::BITS - variants.saturating_sub(1).leading_zeros();
+        width_check.extend(quote! {
+            ::core::assert!(
+                <#widest_payload as #krate::bitstart::BitStart>::START
+                    <= <#inferred_next as #krate::bitstart::BitStart>::START + #fixed_width,
+                "prefix tags use more bits than a fixed-width tag",
+            );
+        });
+
+        let codes = (0..variants as u128).collect();
+        let encoding = Encoding::Prefix {
+            ty: target_ty,
+            variants,
+        };
+        (codes, layouts, encoding, next_bitstart)
+    } else {
+        // How many bits are required for the discriminant.
+        let codes = discriminant_codes(data, attrs.discr_repr.as_ref())?;
+        let max_code = codes.iter().copied().max().unwrap_or(0);
+        // Whether the discriminant fits is checked on the target, as it may
+        // have a different pointer width than the machine running the derive.
+        let discr_bits = 128 - max_code.leading_zeros();
+        let discr_ty = quote!(#krate::impls::UInt<#discr_bits>);
+        let span = data
+            .variants
+            .iter()
+            .zip(&codes)
+            .find(|(_, &code)| code == max_code)
+            .map_or_else(Span::call_site, |(variant, _)| {
+                match &variant.discriminant {
+                    Some((_, expr)) => expr.span(),
+                    None => variant.ident.span(),
+                }
+            });
+        target_checks.extend(quote_spanned! {span=>
+            const _: () = ::core::assert!(
+                #discr_bits <= ::core::primitive::usize::BITS,
+                "discriminant is too large to be packed into a pointer-sized integer",
+            );
+        });
+
+        // Each variant's fields are laid out as a separate struct starting at
+        // `payload_bitstart`, overlapping one another. By default, the
+        // discriminant is placed after the widest variant, but it may instead
+        // be placed before all variants, or at a fixed bit below them.
+        let tag = attrs.tag.map_or(TagPlacement::Low, |(tag, _)| tag);
+        let payload_bitstart = match tag {
+            TagPlacement::Low | TagPlacement::Bit(_) => quote!(_PackStart),
+            TagPlacement::High => quote!(#krate::bitstart::NextStart<_PackStart, #discr_ty>),
+            TagPlacement::Prefix => unreachable!(),
+        };
+        let layouts: Vec<_> = data
+            .variants
+            .iter()
+            .map(|variant| fields_layout(krate, &variant.fields, payload_bitstart.clone()))
+            .collect::<Result<_, _>>()?;
+
+        let mut payload_end = payload_bitstart.clone();
+        for layout in &layouts {
+            let after_bitstart = &layout.next_bitstart;
+            payload_end = quote!(#krate::bitstart::UnionStart<#payload_end, #after_bitstart>);
+        }
+        let (discr_bitstart, next_bitstart) = match tag {
+            TagPlacement::Low => (
+                payload_end.clone(),
+                quote!(#krate::bitstart::NextStart<#payload_end, #discr_ty>),
+            ),
+            TagPlacement::High => (quote!(_PackStart), payload_end),
+            TagPlacement::Bit(bit) => {
+                let inferred_end = infer_lifetimes(&payload_end);
+                let tag_start = bit.saturating_add(discr_bits);
+                width_check.extend(quote! {
+                    ::core::assert!(
+                        #tag_start <= <#inferred_end as #krate::bitstart::BitStart>::START,
+                        "variant payloads overlap the fixed-position tag",
+                    );
                 });
+                (
+                    quote!(#krate::bitstart::ReprStart<#tag_start>),
+                    quote!(#krate::bitstart::ReprStart<#bit>),
+                )
             }
+            TagPlacement::Prefix => unreachable!(),
+        };
+        let encoding = Encoding::Tag {
+            bitstart: discr_bitstart,
+            ty: discr_ty,
+        };
+        (codes, layouts, encoding, next_bitstart)
+    };
+
+    // The `Packed*Ref` view enum borrows the packed value, and has a variant
+    // for each variant in the original enum, holding references to the packed
+    // representation of each field.
+    let view_name = format_ident!("Packed{}Ref", name);
+    let mut view_generics = generics.clone();
+    view_generics.params.insert(0, parse_quote!('_pack));
+    {
+        // Every parameter must outlive the borrow of the packed value.
+        let bounds: Vec<WherePredicate> = generics
+            .params
+            .iter()
+            .filter_map(|param| match param {
+                GenericParam::Lifetime(def) => {
+                    let lt = &def.lifetime;
+                    Some(parse_quote!(#lt: '_pack))
+                }
+                GenericParam::Type(param) => {
+                    let ident = &param.ident;
+                    Some(parse_quote!(#ident: '_pack))
+                }
+                GenericParam::Const(_) => None,
+            })
+            .collect();
+        view_generics.make_where_clause().predicates.extend(bounds);
+    }
+    let (_, view_type_generics, view_where_clause) = view_generics.split_for_impl();
+
+    let read_discr = encoding.read(krate, quote!(self.inner.as_raw()));
+    let read_discr = quote!(unsafe { #read_discr });
+
+    let mut helper_impls = TokenStream::new();
+    let mut view_tys = Vec::new();
+    let mut eq_arms = TokenStream::new();
+    let mut hash_arms = TokenStream::new();
+    let mut debug_arms = TokenStream::new();
+    let mut visit_arms = TokenStream::new();
+    let mut view_variants = TokenStream::new();
+    let mut view_arms = TokenStream::new();
+    let mut store_arms = TokenStream::new();
+    let mut load_arms = TokenStream::new();
+    let mut fits_arms = TokenStream::new();
+    let mut variants_table = TokenStream::new();
+    for ((variant, layout), code) in data.variants.iter().zip(&layouts).zip(codes) {
+        let variant_name = &variant.ident;
+        let tag_layout = encoding.layout(krate, code);
+        let code = Literal::u128_unsuffixed(code);
+
+        let FieldsLayout {
+            fields,
+            bindings,
+            stored_bindings,
+            layout_table,
+            store_impl,
+            load_impl,
+            fits_impl,
+            ..
+        } = layout;
+
+        // Layout Table
+        let variant_name_s = variant_name.to_string();
+        variants_table.extend(quote! {
+            #krate::layout::VariantLayout {
+                name: #variant_name_s,
+                #tag_layout
+                fields: &[#layout_table],
+            },
+        });
+
+        // Comparison Impls
+        let views = field_views(krate, &variant.fields, fields, &variant_name_s);
+        let FieldViews {
+            eq,
+            hash,
+            debug,
+            visit,
+            ..
+        } = &views;
+        eq_arms.extend(quote!(#code => #eq,));
+        hash_arms.extend(quote!(#code => { #hash }));
+        debug_arms.extend(quote!(#code => #debug,));
+        visit_arms.extend(quote! {
+            #code => {
+                visitor.visit_variant(#variant_name_s);
+                #visit
+            }
+        });
+        view_tys.extend(views.tys);
+
+        // Variant Accessor Methods
+        let snake_name = snake_case(&variant_name.to_string());
+        let is_variant = format_ident!("is_{}", snake_name, span = variant_name.span());
+        helper_impls.extend(quote! {
+            #vis fn #is_variant(&self) -> bool {
+                let discr = #read_discr;
+                discr == #code
+            }
+        });
+
+        let mut packed_tys = Vec::new();
+        let mut packed_refs = Vec::new();
+        let mut field_tys = Vec::new();
+        let mut unpacked_loads = TokenStream::new();
+        let mut unpacked_stores = TokenStream::new();
+        let mut unpacked_args = Vec::new();
+        let mut view_fields = TokenStream::new();
+        let mut view_lets = TokenStream::new();
+        for LaidOutField {
+            field,
+            fname_s,
+            ty,
+            narrowed,
+            bitstart,
+        } in fields
+        {
+            let varname = format_ident!("_field_{}", fname_s, span = field.span());
+            let packed_ty = quote!(<#ty as #krate::Packable<#bitstart>>::Packed);
+            let packed_ref = quote! {
+                unsafe { self.inner.as_field::<#bitstart, #ty>().as_packed() }
+            };
+            let (narrow, widen) = if *narrowed {
+                (quote!(<#ty>::new_unchecked(#varname)), quote!(.get()))
+            } else {
+                (quote!(#varname), quote!())
+            };
+            unpacked_loads.extend(quote! {
+                let mut #varname = raw.read_field::<#bitstart, #ty>()#widen;
+            });
+            unpacked_stores.extend(quote! {
+                raw.write_field::<#bitstart, #ty>(#narrow);
+            });
+            unpacked_args.push(quote!(&mut #varname));
+            field_tys.push(&field.ty);
+            view_lets.extend(quote!(let #varname = #packed_ref;));
+            view_fields.extend(match &field.ident {
+                Some(fname) => quote!(#fname: &'_pack #packed_ty,),
+                None => quote!(&'_pack #packed_ty,),
+            });
+            packed_tys.push(packed_ty);
+            packed_refs.push(packed_ref);
+        }
+        view_variants.extend(match &variant.fields {
+            Fields::Named(_) => quote!(#variant_name { #view_fields },),
+            Fields::Unnamed(_) => quote!(#variant_name(#view_fields),),
+            Fields::Unit => quote!(#variant_name,),
+        });
+        view_arms.extend(quote! {
+            #code => {
+                #view_lets
+                #view_name::#variant_name #stored_bindings
+            }
+        });
+
+        let as_variant = format_ident!("as_{}", snake_name, span = variant_name.span());
+        let as_variant_mut = format_ident!("as_{}_mut", snake_name, span = variant_name.span());
+        if fields.len() == 1 {
+            let ty = &fields[0].ty;
+            let bitstart = &fields[0].bitstart;
+            let packed_ty = &packed_tys[0];
+            helper_impls.extend(quote! {
+                #vis fn #as_variant(&self) -> ::core::option::Option<&#packed_ty> {
+                    if self.#is_variant() {
+                        ::core::option::Option::Some(unsafe {
+                            self.inner.as_field::<#bitstart, #ty>().as_packed()
+                        })
+                    } else {
+                        ::core::option::Option::None
+                    }
+                }
+
+                #vis fn #as_variant_mut(&mut self) -> ::core::option::Option<&mut #packed_ty> {
+                    if self.#is_variant() {
+                        ::core::option::Option::Some(unsafe {
+                            self.inner.as_field_mut::<#bitstart, #ty>().as_packed_mut()
+                        })
+                    } else {
+                        ::core::option::Option::None
+                    }
+                }
+            });
+        } else if !packed_tys.is_empty() {
+            // Handing out multiple `&mut` references into the same word would
+            // alias, so variants with multiple fields are instead modified
+            // through unpacked copies of their fields, which are written back
+            // once the closure returns.
+            let with_variant_mut =
+                format_ident!("with_{}_mut", snake_name, span = variant_name.span());
+            helper_impls.extend(quote! {
+                #vis fn #as_variant(&self) -> ::core::option::Option<(#(&#packed_tys,)*)> {
+                    if self.#is_variant() {
+                        ::core::option::Option::Some((#(#packed_refs,)*))
+                    } else {
+                        ::core::option::Option::None
+                    }
+                }
+
+                #vis fn #with_variant_mut<__R>(
+                    &mut self,
+                    f: impl ::core::ops::FnOnce(#(&mut #field_tys),*) -> __R,
+                ) -> ::core::option::Option<__R>
+                where
+                    #(for<'__a> #field_tys: ::core::marker::Copy,)*
+                {
+                    if !self.#is_variant() {
+                        return ::core::option::Option::None;
+                    }
+                    let raw = self.inner.as_raw_mut();
+                    unsafe {
+                        #unpacked_loads
+                        let result = f(#(#unpacked_args),*);
+                        #unpacked_stores
+                        ::core::option::Option::Some(result)
+                    }
+                }
+            });
         }
+        store_arms.extend(quote! {
+            #name::#variant_name #bindings => {
+                #store_impl
+                #code
+            }
+        });
+        load_arms.extend(quote! {
+            #code => {
+                #load_impl
+                #name::#variant_name #bindings
+            }
+        });
+        fits_arms.extend(quote! {
+            #name::#variant_name #bindings => #fits_impl,
+        });
     }
 
-    let store_impl = quote! {
-        let discr = match self {
-            #store_arms
-        };
-        _pack.write_field::<#discr_bitstart, #discr_ty>(discr);
+    helper_impls.extend(quote! {
+        /// The tag and field positions of each variant within the packed
+        /// value.
+        pub const VARIANTS: &'static [#krate::layout::VariantLayout] = &[#variants_table];
+    });
+
+    // Uninhabited enums have nothing to store, and writing the discriminant
+    // would be unreachable code.
+    let store_impl = if data.variants.is_empty() {
+        quote!(match self {})
+    } else {
+        let write_discr = encoding.write(krate, quote!(_pack));
+        quote! {
+            let discr: usize = match self {
+                #store_arms
+            };
+            #write_discr
+        }
     };
 
+    let fits_impl = if data.variants.is_empty() {
+        quote!(match *self {})
+    } else {
+        quote! {
+            match self {
+                #fits_arms
+            }
+        }
+    };
+
+    // The codes of both values are compared before their fields, which are
+    // only known to be in the same place if the variants match.
+    let (eq_impl, hash_impl, debug_impl, visit_impl) = if data.variants.is_empty() {
+        let unreachable = quote!(::core::unreachable!());
+        (
+            unreachable.clone(),
+            unreachable.clone(),
+            unreachable.clone(),
+            unreachable,
+        )
+    } else {
+        let read_other_discr = encoding.read(krate, quote!(other.inner.as_raw()));
+        (
+            quote! {
+                let discr = #read_discr;
+                let other_discr = unsafe { #read_other_discr };
+                discr == other_discr
+                    && match discr {
+                        #eq_arms
+                        _ => ::core::panic!("invalid packed discriminant"),
+                    }
+            },
+            quote! {
+                let discr = #read_discr;
+                ::core::hash::Hash::hash(&discr, state);
+                match discr {
+                    #hash_arms
+                    _ => ::core::panic!("invalid packed discriminant"),
+                }
+            },
+            quote! {
+                match #read_discr {
+                    #debug_arms
+                    _ => ::core::panic!("invalid packed discriminant"),
+                }
+            },
+            quote! {
+                match #read_discr {
+                    #visit_arms
+                    _ => ::core::panic!("invalid packed discriminant"),
+                }
+            },
+        )
+    };
+
+    let read_discr_impl = encoding.read(krate, quote!(_pack));
     let load_impl = quote! {
-        let discr = _pack.read_field::<#discr_bitstart, #discr_ty>();
-        match discr.get() {
+        match #read_discr_impl {
             #load_arms
-            _ => ::core::hint::unreachable_unchecked(),
+            _ => ::core::panic!("invalid packed discriminant"),
         }
     };
 
+    // Enums without any fields have nothing to view, and the view enum would
+    // have unused generic parameters, so it is omitted.
+    let mut helper_items = target_checks;
+    helper_items.extend(tag_items);
+    if layouts.iter().any(|layout| !layout.fields.is_empty()) {
+        helper_impls.extend(quote! {
+            /// Borrow the packed value as an enum which can be matched on.
+            #vis fn view<'_pack>(&'_pack self) -> #view_name #view_type_generics {
+                match #read_discr {
+                    #view_arms
+                    _ => ::core::panic!("invalid packed discriminant"),
+                }
+            }
+        });
+
+        helper_items.extend(quote! {
+            #vis enum #view_name #view_generics #view_where_clause {
+                #view_variants
+            }
+        });
+    }
+
     Ok(Impls {
         load_impl,
         store_impl,
-        helper_impls: TokenStream::new(),
+        fits_impl,
+        width_check,
+        helper_items,
+        helper_impls,
         next_bitstart,
+        view_tys,
+        eq_impl,
+        hash_impl,
+        debug_impl,
+        visit_impl,
     })
 }
 
+/// Additional code for the helper type, used by other derives building on
+/// `Packable`.
+#[derive(Default)]
+pub struct HelperExtras {
+    /// Extra items within the helper's `impl` block.
+    pub helper_impls: TokenStream,
+    /// Body of the helper's `Debug::fmt`, replacing the default.
+    pub debug_impl: Option<TokenStream>,
+}
+
 pub fn do_derive_packable(input: &DeriveInput) -> Result<TokenStream, Error> {
-    // Introduce two additional generics for the impl.
-    let mut generics = input.generics.clone();
-    generics
-        .params
-        .push(parse_quote!(_PackStart: ptrpack::bitstart::BitStart));
+    do_derive_packable_with(input, HelperExtras::default())
+}
 
+pub fn do_derive_packable_with(
+    input: &DeriveInput,
+    extras: HelperExtras,
+) -> Result<TokenStream, Error> {
     let name = &input.ident;
     let vis = &input.vis;
+    let attrs = ContainerAttrs::parse(&input.attrs)?;
+    let krate = &attrs.krate();
+
+    // Introduce two additional generics for the impl.
+    let mut generics = input.generics.clone();
+    generics.params.push(
+        parse_quote!(_PackStart: #krate::bitstart::BitStart = #krate::bitstart::DefaultStart),
+    );
 
     let Impls {
+        helper_items,
         helper_impls,
         store_impl,
         load_impl,
+        fits_impl,
+        width_check,
         next_bitstart,
+        view_tys,
+        eq_impl,
+        hash_impl,
+        debug_impl,
+        visit_impl,
     } = match &input.data {
-        Data::Struct(data) => struct_data(name, data)?,
-        Data::Enum(data) => enum_data(name, data)?,
+        Data::Struct(data) => {
+            if let Some((_, span)) = attrs.tag {
+                return Err(Error::new(span, "`tag` is only supported on enums"));
+            }
+            if let Some(span) = attrs.niche {
+                return Err(Error::new(span, "`niche` is only supported on enums"));
+            }
+            if let Some(repr) = &attrs.repr {
+                if attrs.transparent.is_some() {
+                    return Err(Error::new_spanned(
+                        repr,
+                        "`repr` and `transparent` can't be used together",
+                    ));
+                }
+                if !input.generics.params.is_empty() {
+                    return Err(Error::new_spanned(
+                        &input.generics,
+                        "`repr` is unsupported on generic types",
+                    ));
+                }
+            }
+            match attrs.transparent {
+                Some(span) => transparent_data(krate, span, name, data)?,
+                None => struct_data(name, vis, &generics, &attrs, data)?,
+            }
+        }
+        Data::Enum(data) => {
+            if let Some(span) = attrs.transparent {
+                return Err(Error::new(
+                    span,
+                    "`transparent` is only supported on structs",
+                ));
+            }
+            if let Some(repr) = &attrs.repr {
+                return Err(Error::new_spanned(
+                    repr,
+                    "`repr` is only supported on structs",
+                ));
+            }
+            enum_data(name, vis, &generics, &attrs, data)?
+        }
         Data::Union(_) => {
             return Err(Error::new_spanned(input, "union types are unsupported"));
         }
     };
 
-    // Unfortunately, using the full types for members such as `&'a T` when
-    // computing the `WIDTH` constant produces a compiler error, where the
-    // compiler complains it cannot ensure `T` outlives `&'a`. This constraint
-    // should be enforced already, as the type exists as a field in the struct
-    // we're implementing, but the compiler appears to be unaware.
-    //
-    // This `fold` pass patches up the `last_bitstart` value to use inferred
-    // lifetimes, which dodges this well-formedness issue.
-    //
-    // FIXME: This probably barfs on `for<'a> ...`-style expressions, and
-    // perhaps that should be fixed?
-    struct InferLifetimes;
-    impl Fold for InferLifetimes {
-        fn fold_lifetime(&mut self, l: Lifetime) -> Lifetime {
-            if &l.ident == "static" {
-                return l;
-            }
-            Lifetime::new("'_", l.apostrophe)
-        }
-    }
-    let next_bitstart = InferLifetimes.fold_type(parse_quote!(#next_bitstart));
+    let next_bitstart = infer_lifetimes(&next_bitstart);
 
     // Get the generics required for the impl.
     let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
@@ -255,7 +1727,78 @@ pub fn do_derive_packable(input: &DeriveInput) -> Result<TokenStream, Error> {
     let helper_name = format_ident!("Packed{}", name);
     let helper_ty = quote!(#helper_name #type_generics);
     let target_ty = quote!(#name #base_type_generics);
-    let subpack_ty = quote!(ptrpack::SubPack<_PackStart, #target_ty>);
+    let subpack_ty = quote!(#krate::SubPack<_PackStart, #target_ty>);
+    let packed_ty = match attrs.transparent {
+        Some(_) => subpack_ty.clone(),
+        None => helper_ty.clone(),
+    };
+
+    let packable_impl = quote! {
+        unsafe impl #impl_generics #krate::Packable<_PackStart> for #target_ty #where_clause {
+            type Packed = #packed_ty;
+
+            const WIDTH: u32 = {
+                #width_check
+                let old_start = _PackStart::START;
+                let new_start = <#next_bitstart as #krate::bitstart::BitStart>::START;
+                old_start - new_start
+            };
+
+            fn fits(&self) -> bool {
+                #fits_impl
+            }
+
+            unsafe fn store(self, _pack: &mut #krate::RawPackedBits<_PackStart, Self>) {
+                #store_impl
+            }
+
+            unsafe fn load(_pack: &#krate::RawPackedBits<_PackStart, Self>) -> Self {
+                #load_impl
+            }
+        }
+    };
+
+    // The helper's comparison and formatting impls are only available when
+    // the packed view of every field supports them.
+    let bounded = |bound: TokenStream| {
+        let mut generics = generics.clone();
+        let predicates = view_tys
+            .iter()
+            .map(|ty| -> WherePredicate { parse_quote!(#ty: #bound) });
+        generics.make_where_clause().predicates.extend(predicates);
+        generics.where_clause
+    };
+    let eq_where = bounded(quote!(::core::cmp::PartialEq));
+    let full_eq_where = bounded(quote!(::core::cmp::Eq));
+    let hash_where = bounded(quote!(::core::hash::Hash));
+    let visit_where = bounded(quote!(#krate::visit::Visitable));
+
+    let HelperExtras {
+        helper_impls: extra_helper_impls,
+        debug_impl: extra_debug_impl,
+    } = extras;
+    let (debug_impl, debug_where) = match extra_debug_impl {
+        Some(debug_impl) => (debug_impl, generics.where_clause.clone()),
+        None => (debug_impl, bounded(quote!(::core::fmt::Debug))),
+    };
+
+    // Transparent types are accessed directly through `SubPack`, so don't
+    // need a helper type.
+    if attrs.transparent.is_some() {
+        return Ok(quote! {
+            #packable_impl
+
+            impl #impl_generics #krate::visit::VisitPacked<_PackStart> for #target_ty #visit_where {
+                fn visit_packed(
+                    packed: &#subpack_ty,
+                    visitor: &mut dyn #krate::visit::PackVisitor,
+                ) {
+                    #visit_impl
+                }
+            }
+        });
+    }
+
     let result = quote! {
         #vis struct #helper_name #generics #where_clause {
             inner: #subpack_ty,
@@ -263,8 +1806,11 @@ pub fn do_derive_packable(input: &DeriveInput) -> Result<TokenStream, Error> {
 
         impl #impl_generics #helper_ty #where_clause {
             #helper_impls
+            #extra_helper_impls
         }
 
+        #helper_items
+
         // XXX: It's pretty gross that we're using `Deref` for a kind-of
         // "inheritance" here. I'd love to do something better, but it doesn't
         // look to be possible without losing `Copy`-only methods, due to rustc
@@ -286,29 +1832,40 @@ pub fn do_derive_packable(input: &DeriveInput) -> Result<TokenStream, Error> {
             }
         }
 
-        impl #impl_generics ::core::fmt::Debug for #helper_ty #where_clause {
-            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
-                self.inner.fmt(f)
+        impl #impl_generics ::core::cmp::PartialEq for #helper_ty #eq_where {
+            fn eq(&self, other: &Self) -> bool {
+                #eq_impl
             }
         }
 
-        unsafe impl #impl_generics ptrpack::Packable<_PackStart> for #target_ty #where_clause {
-            type Packed = #helper_ty;
+        impl #impl_generics ::core::cmp::Eq for #helper_ty #full_eq_where {}
 
-            const WIDTH: u32 = {
-                let old_start = _PackStart::START;
-                let new_start = <#next_bitstart as ptrpack::bitstart::BitStart>::START;
-                old_start - new_start
-            };
+        impl #impl_generics ::core::hash::Hash for #helper_ty #hash_where {
+            fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                #hash_impl
+            }
+        }
 
-            unsafe fn store(self, _pack: &mut ptrpack::RawPackedBits<_PackStart, Self>) {
-                #store_impl
+        impl #impl_generics ::core::fmt::Debug for #helper_ty #debug_where {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                #debug_impl
             }
+        }
 
-            unsafe fn load(_pack: &ptrpack::RawPackedBits<_PackStart, Self>) -> Self {
-                #load_impl
+        impl #impl_generics #helper_ty #visit_where {
+            /// Report the contents of the packed value to `visitor`.
+            #vis fn visit(&self, visitor: &mut impl #krate::visit::PackVisitor) {
+                #krate::visit::Visitable::visit(self, visitor)
+            }
+        }
+
+        impl #impl_generics #krate::visit::Visitable for #helper_ty #visit_where {
+            fn visit(&self, visitor: &mut dyn #krate::visit::PackVisitor) {
+                #visit_impl
             }
         }
+
+        #packable_impl
     };
     Ok(result)
 }
@@ -1,3 +1,4 @@
+use crate::flags::do_derive_flags;
 use crate::packable::do_derive_packable;
 use syn::{parse_quote, DeriveInput};
 
@@ -26,10 +27,208 @@ fn my_test() {
     child
         .stdin
         .unwrap()
-        .write(multiline_output.as_bytes())
+        .write_all(multiline_output.as_bytes())
         .unwrap();
     child.stdin = None;
     child.wait().unwrap();
 
     panic!();
 }
+
+#[test]
+fn oversized_discriminant() {
+    let input: DeriveInput = parse_quote! {
+        #[repr(u128)]
+        enum Huge {
+            Small = 0,
+            Large = 0x1_0000_0000_0000_0000,
+        }
+    };
+
+    // The discriminant may fit on some targets, so it's checked by the
+    // generated code against the target's pointer width.
+    let output = do_derive_packable(&input).unwrap().to_string();
+    assert!(output.contains("discriminant is too large"));
+    assert!(output.contains("usize :: BITS"));
+}
+
+#[test]
+fn non_literal_discriminant() {
+    let input: DeriveInput = parse_quote! {
+        enum Computed {
+            A = BASE,
+            B = BASE * 2,
+        }
+    };
+
+    let error = do_derive_packable(&input).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("explicit discriminants must be integer literals"));
+}
+
+#[test]
+fn negative_discriminant_without_signed_repr() {
+    let input: DeriveInput = parse_quote! {
+        #[repr(u8)]
+        enum Step {
+            Back = -1,
+            Stay,
+        }
+    };
+
+    let error = do_derive_packable(&input).unwrap_err();
+    assert!(error.to_string().contains("require a signed `#[repr]`"));
+}
+
+#[test]
+fn niche_with_explicit_discriminants() {
+    let input: DeriveInput = parse_quote! {
+        #[ptrpack(niche)]
+        enum Bad<'a> {
+            A(&'a u32),
+            B = 5,
+        }
+    };
+
+    let error = do_derive_packable(&input).unwrap_err();
+    assert!(error.to_string().contains("unsupported with `niche`"));
+}
+
+#[test]
+fn unknown_field_attribute() {
+    let input: DeriveInput = parse_quote! {
+        struct Bad {
+            #[ptrpack(bytes = 4)]
+            field: u32,
+        }
+    };
+
+    let error = do_derive_packable(&input).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("unknown ptrpack field attribute"));
+}
+
+#[test]
+fn skip_with_default() {
+    let input: DeriveInput = parse_quote! {
+        struct Cached {
+            #[ptrpack(skip, default = "7")]
+            seven: u8,
+            #[ptrpack(default = "7", skip)]
+            other: u8,
+            flag: bool,
+        }
+    };
+
+    let output = do_derive_packable(&input).unwrap().to_string();
+    assert!(!output.contains("compile_error"));
+
+    let input: DeriveInput = parse_quote! {
+        struct Bad {
+            #[ptrpack(default = "7", default = "8")]
+            seven: u8,
+        }
+    };
+
+    let error = do_derive_packable(&input).unwrap_err();
+    assert!(error.to_string().contains("duplicate `default` attribute"));
+}
+
+#[test]
+fn tag_on_struct() {
+    let input: DeriveInput = parse_quote! {
+        #[ptrpack(tag = "high")]
+        struct NotAnEnum {
+            field: u32,
+        }
+    };
+
+    let error = do_derive_packable(&input).unwrap_err();
+    assert!(error.to_string().contains("only supported on enums"));
+}
+
+#[test]
+fn invalid_tag_bit() {
+    let input: DeriveInput = parse_quote! {
+        #[ptrpack(tag = "bit:four")]
+        enum Bad {
+            A(bool),
+            B,
+        }
+    };
+
+    let error = do_derive_packable(&input).unwrap_err();
+    assert!(error.to_string().contains("`\"bit:N\"`"));
+}
+
+#[test]
+fn niche_with_multiple_data_variants() {
+    let input: DeriveInput = parse_quote! {
+        #[ptrpack(niche)]
+        enum Bad<'a> {
+            A(&'a u32),
+            B(&'a u32),
+            C,
+        }
+    };
+
+    let error = do_derive_packable(&input).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("exactly one variant with fields"));
+}
+
+#[test]
+fn prefix_with_explicit_discriminants() {
+    let input: DeriveInput = parse_quote! {
+        #[ptrpack(tag = "prefix")]
+        enum Bad {
+            A(u8) = 1,
+            B,
+        }
+    };
+
+    let error = do_derive_packable(&input).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("explicit discriminants are unsupported"));
+}
+
+#[test]
+fn transparent_with_multiple_fields() {
+    let input: DeriveInput = parse_quote! {
+        #[ptrpack(transparent)]
+        struct Pair(u8, u8);
+    };
+
+    let error = do_derive_packable(&input).unwrap_err();
+    assert!(error.to_string().contains("exactly one stored field"));
+}
+
+#[test]
+fn repr_with_signed_integer() {
+    let input: DeriveInput = parse_quote! {
+        #[ptrpack(repr = "i16")]
+        struct Status {
+            ready: bool,
+        }
+    };
+
+    let error = do_derive_packable(&input).unwrap_err();
+    assert!(error.to_string().contains("unsigned integer type"));
+}
+
+#[test]
+fn flags_with_non_bool_field() {
+    let input: DeriveInput = parse_quote! {
+        struct Perms {
+            read: bool,
+            mode: u8,
+        }
+    };
+
+    let error = do_derive_flags(&input).unwrap_err();
+    assert!(error.to_string().contains("plain `bool`s"));
+}
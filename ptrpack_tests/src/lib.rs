@@ -0,0 +1 @@
+//! Integration tests for `ptrpack` which depend on it under another name.
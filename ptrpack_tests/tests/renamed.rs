@@ -0,0 +1,43 @@
+use facade::bitstart::DefaultStart;
+use facade::{Pack, Packable};
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(crate = "facade")]
+struct Renamed<'a> {
+    value: &'a u32,
+    #[ptrpack(bits = 1)]
+    flag: u8,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(crate = "::facade", tag = "prefix")]
+enum RenamedEnum<'a> {
+    Ref(&'a u32),
+    Byte(u8),
+    Nothing,
+}
+
+#[test]
+fn test_renamed_crate() {
+    assert_eq!(
+        <Renamed as Packable<DefaultStart>>::WIDTH,
+        <&u32 as Packable<DefaultStart>>::WIDTH + 1
+    );
+
+    let value = 5;
+    let renamed = Renamed {
+        value: &value,
+        flag: 1,
+    };
+    let packed = Pack::new(renamed);
+    assert_eq!(packed.get(), renamed);
+    assert_eq!(packed.with_flag(0).flag(), 0);
+
+    for &value in &[
+        RenamedEnum::Ref(&value),
+        RenamedEnum::Byte(7),
+        RenamedEnum::Nothing,
+    ] {
+        assert_eq!(Pack::new(value).get(), value);
+    }
+}
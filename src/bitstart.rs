@@ -29,6 +29,21 @@ impl BitStart for DefaultStart {
 }
 impl sealed::Sealed for DefaultStart {}
 
+/// The initial starting point used for values packed into the low `BITS`
+/// bits of an integer, such as by `#[ptrpack(repr = "...")]`. Also used to
+/// place the tag of a `#[ptrpack(tag = "bit:N")]` enum at a fixed bit.
+pub struct ReprStart<const BITS: u32>(());
+impl<const BITS: u32> BitStart for ReprStart<BITS> {
+    const START: u32 = {
+        assert!(
+            BITS <= PTR_WIDTH,
+            "`ReprStart<BITS>` may not be wider than a pointer"
+        );
+        BITS
+    };
+}
+impl<const BITS: u32> sealed::Sealed for ReprStart<BITS> {}
+
 /// Advance bitstart `S` over a value `P`.
 pub struct NextStart<S, P>(S, P);
 impl<S, P> BitStart for NextStart<S, P>
@@ -54,3 +69,31 @@ where
     const START: u32 = const_min(A::START, B::START);
 }
 impl<A, B> sealed::Sealed for UnionStart<A, B> {}
+
+/// The tag identifying one variant of a prefix-coded enum.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PrefixTag {
+    /// The value of the tag bits.
+    pub code: usize,
+    /// The number of bits in the tag.
+    pub width: u32,
+}
+
+/// Enums with `#[ptrpack(tag = "prefix")]`, which store a variable-length
+/// tag in their most significant bits. Implemented by `#[derive(Packable)]`.
+pub trait PrefixTags<S: BitStart> {
+    /// The tag of each variant, in declaration order.
+    const TAGS: &'static [PrefixTag];
+}
+
+/// The start of the payload of variant `IDX` of the prefix-coded enum `E`,
+/// directly after its tag.
+pub struct PrefixStart<S, E, const IDX: usize>(S, E);
+impl<S, E, const IDX: usize> BitStart for PrefixStart<S, E, IDX>
+where
+    S: BitStart,
+    E: PrefixTags<S>,
+{
+    const START: u32 = S::START - E::TAGS[IDX].width;
+}
+impl<S, E, const IDX: usize> sealed::Sealed for PrefixStart<S, E, IDX> {}
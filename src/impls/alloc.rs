@@ -1,9 +1,12 @@
 //! Helper types and impls only used if the `alloc` feature is enabled.
 
 use crate::bitstart::BitStart;
-use crate::{Packable, RawPackedBits, SubPack};
+use crate::visit::{BitsKind, PackVisitor, Visitable};
+use crate::{pointer_niches, NichePackable, NonZeroPackable, Packable, RawPackedBits, SubPack};
 
 use alloc::boxed::Box;
+use core::fmt;
+use core::hash::{Hash, Hasher};
 use core::mem;
 use core::ops::{Deref, DerefMut};
 
@@ -11,18 +14,39 @@ pub struct PackedBox<S, T> {
     inner: SubPack<S, Box<T>>,
 }
 
-impl<S: BitStart, T> PackedBox<S, T> {
-    /*
+impl<S: BitStart, T> AsRef<T> for PackedBox<S, T> {
     /// Get the `Box<T>` value as `&T`
-    pub fn as_ref(&self) -> &T {
-        unsafe { &*(self.inner.get_as_high_bits() as *const T) }
+    fn as_ref(&self) -> &T {
+        unsafe { &*(self.inner.as_raw().read_high_bits() as *const T) }
     }
+}
 
+impl<S: BitStart, T> AsMut<T> for PackedBox<S, T> {
     /// Get the `Box<T>` value as `&mut T`
-    pub fn as_mut(&mut self) -> &mut T {
-        unsafe { &mut *(self.inner.get_as_high_bits() as *mut T) }
+    fn as_mut(&mut self) -> &mut T {
+        unsafe { &mut *(self.inner.as_raw().read_high_bits() as *mut T) }
+    }
+}
+
+// Like `Box<T>`, comparisons and formatting forward to the boxed value.
+impl<S: BitStart, T: PartialEq> PartialEq for PackedBox<S, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl<S: BitStart, T: Eq> Eq for PackedBox<S, T> {}
+
+impl<S: BitStart, T: Hash> Hash for PackedBox<S, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_ref().hash(state)
+    }
+}
+
+impl<S: BitStart, T: fmt::Debug> fmt::Debug for PackedBox<S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_ref().fmt(f)
     }
-    */
 }
 
 impl<S, T> Deref for PackedBox<S, T> {
@@ -54,3 +78,16 @@ unsafe impl<S: BitStart, T> Packable<S> for Box<T> {
         Box::from_raw(p.read_high_bits() as *mut T)
     }
 }
+
+impl<S: BitStart, T> Visitable for PackedBox<S, T> {
+    fn visit(&self, visitor: &mut dyn PackVisitor) {
+        visitor.visit_bits(BitsKind::Box, self.inner.as_raw().read_high_bits());
+    }
+}
+
+// `Box` pointers are never null, even for zero-sized types.
+unsafe impl<S: BitStart, T> NonZeroPackable<S> for Box<T> {}
+
+unsafe impl<S: BitStart, T> NichePackable<S> for Box<T> {
+    const NICHES: usize = pointer_niches::<T>();
+}
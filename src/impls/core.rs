@@ -1,6 +1,54 @@
 use crate::bitstart::BitStart;
-use crate::{Packable, RawPackedBits, SubPack};
+use crate::visit::{BitsKind, PackVisitor, VisitPacked, Visitable};
+use crate::{
+    pointer_niches, NichePackable, NonZeroPackable, Packable, PlainPackable, RawPackedBits, SubPack,
+};
+use core::marker::PhantomData;
 use core::mem;
+use core::num::{
+    NonZeroI16, NonZeroI32, NonZeroI8, NonZeroIsize, NonZeroU16, NonZeroU32, NonZeroU8,
+    NonZeroUsize,
+};
+#[cfg(target_pointer_width = "64")]
+use core::num::{NonZeroI64, NonZeroU64};
+
+unsafe impl<S: BitStart> Packable<S> for () {
+    type Packed = SubPack<S, ()>;
+
+    const WIDTH: u32 = 0;
+
+    #[inline]
+    unsafe fn store(self, _p: &mut RawPackedBits<S, Self>) {}
+
+    #[inline]
+    unsafe fn load(_p: &RawPackedBits<S, Self>) -> Self {}
+}
+
+unsafe impl<S: BitStart> PlainPackable<S> for () {}
+
+impl<S: BitStart> VisitPacked<S> for () {
+    fn visit_packed(_packed: &SubPack<S, Self>, _visitor: &mut dyn PackVisitor) {}
+}
+
+unsafe impl<S: BitStart, T: ?Sized> Packable<S> for PhantomData<T> {
+    type Packed = SubPack<S, PhantomData<T>>;
+
+    const WIDTH: u32 = 0;
+
+    #[inline]
+    unsafe fn store(self, _p: &mut RawPackedBits<S, Self>) {}
+
+    #[inline]
+    unsafe fn load(_p: &RawPackedBits<S, Self>) -> Self {
+        PhantomData
+    }
+}
+
+unsafe impl<S: BitStart, T: ?Sized> PlainPackable<S> for PhantomData<T> {}
+
+impl<S: BitStart, T: ?Sized> VisitPacked<S> for PhantomData<T> {
+    fn visit_packed(_packed: &SubPack<S, Self>, _visitor: &mut dyn PackVisitor) {}
+}
 
 unsafe impl<S: BitStart> Packable<S> for bool {
     type Packed = SubPack<S, bool>;
@@ -18,6 +66,149 @@ unsafe impl<S: BitStart> Packable<S> for bool {
     }
 }
 
+unsafe impl<S: BitStart> PlainPackable<S> for bool {}
+
+impl<S: BitStart> VisitPacked<S> for bool {
+    fn visit_packed(packed: &SubPack<S, Self>, visitor: &mut dyn PackVisitor) {
+        visitor.visit_bits(BitsKind::Bool, packed.get() as usize);
+    }
+}
+
+unsafe impl<S: BitStart> Packable<S> for char {
+    type Packed = SubPack<S, char>;
+
+    /// Enough bits to store any unicode scalar value (`char::MAX` is
+    /// `0x10FFFF`).
+    const WIDTH: u32 = 21;
+
+    #[inline]
+    unsafe fn store(self, p: &mut RawPackedBits<S, Self>) {
+        p.write_low_bits(self as usize);
+    }
+
+    #[inline]
+    unsafe fn load(p: &RawPackedBits<S, Self>) -> Self {
+        // Neighbouring writers, such as `from_repr`, may leave arbitrary bits
+        // here, so the value is validated rather than trusted.
+        core::char::from_u32(p.read_low_bits() as u32).expect("invalid packed `char` value")
+    }
+}
+
+impl<S: BitStart> VisitPacked<S> for char {
+    fn visit_packed(packed: &SubPack<S, Self>, visitor: &mut dyn PackVisitor) {
+        visitor.visit_bits(BitsKind::Char, packed.get() as usize);
+    }
+}
+
+macro_rules! int_decl {
+    ($(
+        $(#[$attr:meta])*
+        $Int:ident as $Uint:ident;
+    )*) => {$(
+        $(#[$attr])*
+        unsafe impl<S: BitStart> Packable<S> for $Int {
+            type Packed = SubPack<S, $Int>;
+
+            const WIDTH: u32 = $Int::BITS;
+
+            #[inline]
+            unsafe fn store(self, p: &mut RawPackedBits<S, Self>) {
+                // Cast through the unsigned type first to avoid sign-extending
+                // into bits outside of our range.
+                p.write_low_bits(self as $Uint as usize);
+            }
+
+            #[inline]
+            unsafe fn load(p: &RawPackedBits<S, Self>) -> Self {
+                p.read_low_bits() as $Uint as $Int
+            }
+        }
+
+        $(#[$attr])*
+        unsafe impl<S: BitStart> PlainPackable<S> for $Int {}
+
+        $(#[$attr])*
+        impl<S: BitStart> VisitPacked<S> for $Int {
+            fn visit_packed(packed: &SubPack<S, Self>, visitor: &mut dyn PackVisitor) {
+                visitor.visit_bits(int_kind($Int::BITS, $Int::MIN != 0), packed.get() as usize);
+            }
+        }
+    )*}
+}
+
+/// Kind of a native integer type, which is signed if its minimum is nonzero.
+fn int_kind(bits: u32, signed: bool) -> BitsKind {
+    if signed {
+        BitsKind::Signed(bits)
+    } else {
+        BitsKind::Unsigned(bits)
+    }
+}
+
+int_decl! {
+    u8 as u8;
+    u16 as u16;
+    u32 as u32;
+    #[cfg(target_pointer_width = "64")]
+    u64 as u64;
+    usize as usize;
+    i8 as u8;
+    i16 as u16;
+    i32 as u32;
+    #[cfg(target_pointer_width = "64")]
+    i64 as u64;
+    isize as usize;
+}
+
+macro_rules! nonzero_decl {
+    ($(
+        $(#[$attr:meta])*
+        $NonZero:ident($Int:ident as $Uint:ident);
+    )*) => {$(
+        $(#[$attr])*
+        unsafe impl<S: BitStart> Packable<S> for $NonZero {
+            type Packed = SubPack<S, $NonZero>;
+
+            const WIDTH: u32 = $Int::BITS;
+
+            #[inline]
+            unsafe fn store(self, p: &mut RawPackedBits<S, Self>) {
+                p.write_low_bits(self.get() as $Uint as usize);
+            }
+
+            #[inline]
+            unsafe fn load(p: &RawPackedBits<S, Self>) -> Self {
+                $NonZero::new_unchecked(p.read_low_bits() as $Uint as $Int)
+            }
+        }
+
+        $(#[$attr])*
+        unsafe impl<S: BitStart> NonZeroPackable<S> for $NonZero {}
+
+        $(#[$attr])*
+        impl<S: BitStart> VisitPacked<S> for $NonZero {
+            fn visit_packed(packed: &SubPack<S, Self>, visitor: &mut dyn PackVisitor) {
+                visitor.visit_bits(int_kind($Int::BITS, $Int::MIN != 0), packed.get().get() as usize);
+            }
+        }
+    )*}
+}
+
+nonzero_decl! {
+    NonZeroU8(u8 as u8);
+    NonZeroU16(u16 as u16);
+    NonZeroU32(u32 as u32);
+    #[cfg(target_pointer_width = "64")]
+    NonZeroU64(u64 as u64);
+    NonZeroUsize(usize as usize);
+    NonZeroI8(i8 as u8);
+    NonZeroI16(i16 as u16);
+    NonZeroI32(i32 as u32);
+    #[cfg(target_pointer_width = "64")]
+    NonZeroI64(i64 as u64);
+    NonZeroIsize(isize as usize);
+}
+
 unsafe impl<'a, T, S: BitStart> Packable<S> for &'a T {
     type Packed = SubPack<S, &'a T>;
 
@@ -33,3 +224,67 @@ unsafe impl<'a, T, S: BitStart> Packable<S> for &'a T {
         &*(p.read_high_bits() as *const T)
     }
 }
+
+// References are never null, and alignment bits are only ever dropped from the
+// low end, so at least one stored bit is always set.
+unsafe impl<T, S: BitStart> NonZeroPackable<S> for &T {}
+
+unsafe impl<T, S: BitStart> NichePackable<S> for &T {
+    const NICHES: usize = pointer_niches::<T>();
+}
+
+impl<T, S: BitStart> VisitPacked<S> for &T {
+    fn visit_packed(packed: &SubPack<S, Self>, visitor: &mut dyn PackVisitor) {
+        visitor.visit_bits(BitsKind::Ref, packed.get() as *const T as usize);
+    }
+}
+
+// `None` is stored as the all-zero bit pattern, which can never be produced by
+// storing a `T`.
+unsafe impl<S: BitStart, T: NonZeroPackable<S>> Packable<S> for Option<T> {
+    type Packed = SubPack<S, Option<T>>;
+
+    const WIDTH: u32 = T::WIDTH;
+
+    #[inline]
+    fn fits(&self) -> bool {
+        match self {
+            Some(value) => value.fits(),
+            None => true,
+        }
+    }
+
+    #[inline]
+    unsafe fn store(self, p: &mut RawPackedBits<S, Self>) {
+        match self {
+            Some(value) => p.write_field::<S, T>(value),
+            None => p.write_unshifted_bits(0),
+        }
+    }
+
+    #[inline]
+    unsafe fn load(p: &RawPackedBits<S, Self>) -> Self {
+        if p.read_unshifted_bits() == 0 {
+            None
+        } else {
+            Some(p.read_field::<S, T>())
+        }
+    }
+}
+
+// Visited like an enum with `None` and `Some` variants.
+impl<S, T> VisitPacked<S> for Option<T>
+where
+    S: BitStart,
+    T: NonZeroPackable<S>,
+    T::Packed: Visitable,
+{
+    fn visit_packed(packed: &SubPack<S, Self>, visitor: &mut dyn PackVisitor) {
+        if packed.as_raw().read_unshifted_bits() == 0 {
+            visitor.visit_variant("None");
+        } else {
+            visitor.visit_variant("Some");
+            visitor.visit_field("0", unsafe { packed.as_field::<S, T>().as_packed() });
+        }
+    }
+}
@@ -1,10 +1,12 @@
 mod core;
-pub use self::core::*;
 
 #[cfg(feature = "alloc")]
 mod alloc;
 #[cfg(feature = "alloc")]
 pub use self::alloc::*;
 
+mod narrow;
+pub use self::narrow::*;
+
 mod tinyuint;
 pub use self::tinyuint::*;
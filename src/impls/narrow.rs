@@ -0,0 +1,152 @@
+use super::UInt;
+use crate::bitstart::BitStart;
+use crate::visit::{BitsKind, PackVisitor, VisitPacked};
+use crate::{Packable, PlainPackable, RawPackedBits, SubPack, PTR_WIDTH};
+
+/// Native integer types which can be stored in fewer bits than their full
+/// width using [`Narrow`].
+pub trait NarrowInt: Copy + Eq {
+    /// The full width of this type.
+    const BITS: u32;
+
+    /// Whether this type is signed.
+    const SIGNED: bool = false;
+
+    /// Convert to bits. Only the low bits of the result are stored, so signed
+    /// values may be sign-extended.
+    fn to_bits(self) -> usize;
+
+    /// Convert back from the low `width` bits of `bits`, sign-extending
+    /// signed values.
+    fn from_bits(bits: usize, width: u32) -> Self;
+
+    /// Check whether this value can be represented in `width` bits.
+    fn fits_in(self, width: u32) -> bool {
+        let mask = if width >= PTR_WIDTH {
+            usize::MAX
+        } else {
+            (1 << width) - 1
+        };
+        Self::from_bits(self.to_bits() & mask, width) == self
+    }
+}
+
+macro_rules! narrow_decl {
+    ($(unsigned $Uint:ident;)* $(signed $Int:ident;)*) => {
+        $(
+            impl NarrowInt for $Uint {
+                const BITS: u32 = $Uint::BITS;
+
+                fn to_bits(self) -> usize {
+                    self as usize
+                }
+
+                fn from_bits(bits: usize, _width: u32) -> Self {
+                    bits as $Uint
+                }
+            }
+        )*
+        $(
+            impl NarrowInt for $Int {
+                const BITS: u32 = $Int::BITS;
+                const SIGNED: bool = true;
+
+                fn to_bits(self) -> usize {
+                    self as usize
+                }
+
+                fn from_bits(bits: usize, width: u32) -> Self {
+                    // Shift the sign bit into the most significant bit, and
+                    // then arithmetic shift back down to sign-extend.
+                    let shift = PTR_WIDTH - width;
+                    (bits as isize).wrapping_shl(shift).wrapping_shr(shift) as $Int
+                }
+            }
+        )*
+    }
+}
+
+narrow_decl! {
+    unsigned u8;
+    unsigned u16;
+    unsigned u32;
+    unsigned u64;
+    unsigned usize;
+    signed i8;
+    signed i16;
+    signed i32;
+    signed i64;
+    signed isize;
+}
+
+/// A native integer stored in only `N` bits.
+///
+/// This is the type used by `#[ptrpack(bits = N)]` fields in derived types.
+#[repr(transparent)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Narrow<T, const N: u32>(T);
+
+impl<T: NarrowInt, const N: u32> Narrow<T, N> {
+    pub fn new(value: T) -> Option<Self> {
+        if value.fits_in(N) {
+            Some(Narrow(value))
+        } else {
+            None
+        }
+    }
+
+    /// # Preconditions
+    ///
+    /// `value` must fit in `N` bits.
+    pub const unsafe fn new_unchecked(value: T) -> Self {
+        Narrow(value)
+    }
+
+    pub const fn get(&self) -> T {
+        self.0
+    }
+}
+
+unsafe impl<S: BitStart, T: NarrowInt, const N: u32> Packable<S> for Narrow<T, N> {
+    type Packed = SubPack<S, Narrow<T, N>>;
+
+    const WIDTH: u32 = {
+        assert!(
+            N <= PTR_WIDTH,
+            "`Narrow<T, N>` may not be wider than a pointer"
+        );
+        assert!(N <= T::BITS, "`Narrow<T, N>` may not be wider than `T`");
+        N
+    };
+
+    #[inline]
+    fn fits(&self) -> bool {
+        self.0.fits_in(N)
+    }
+
+    #[inline]
+    unsafe fn store(self, p: &mut RawPackedBits<S, Self>) {
+        debug_assert!(self.0.fits_in(N), "value does not fit in {} bits", N);
+        p.write_low_bits(self.0.to_bits() & UInt::<N>::MAX.get());
+    }
+
+    #[inline]
+    unsafe fn load(p: &RawPackedBits<S, Self>) -> Self {
+        Narrow(T::from_bits(p.read_low_bits(), N))
+    }
+}
+
+unsafe impl<S: BitStart, T: NarrowInt, const N: u32> PlainPackable<S> for Narrow<T, N> {
+    const SIGNED: bool = T::SIGNED;
+}
+
+impl<S: BitStart, T: NarrowInt, const N: u32> VisitPacked<S> for Narrow<T, N> {
+    fn visit_packed(packed: &SubPack<S, Self>, visitor: &mut dyn PackVisitor) {
+        let kind = if T::SIGNED {
+            BitsKind::Signed(N)
+        } else {
+            BitsKind::Unsigned(N)
+        };
+        visitor.visit_bits(kind, packed.get().get().to_bits());
+    }
+}
@@ -1,55 +1,227 @@
+use crate::visit::{BitsKind, PackVisitor, VisitPacked};
 use crate::BitStart;
-use crate::{Packable, RawPackedBits, SubPack};
+use crate::{Packable, PlainPackable, RawPackedBits, SubPack, PTR_WIDTH};
+use core::convert::TryFrom;
 use core::fmt;
 
-macro_rules! tiny_decl {
-    ($(
-        $Uint:ident: $width:expr;
-    )*) => {$(
-        /// Helper integer value with a specific size.
-        #[repr(transparent)]
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct $Uint(usize);
-
-        impl $Uint {
-            pub fn new(value: usize) -> Option<Self> {
-                let used_bits = 0usize.leading_zeros() - value.leading_zeros();
-                if used_bits > $width {
-                    return None;
-                }
-                Some($Uint(value))
-            }
-
-            pub unsafe fn new_unchecked(value: usize) -> Self {
-                $Uint(value)
-            }
-
-            pub fn get(&self) -> usize {
-                self.0
-            }
-        }
+/// Helper unsigned integer value which fits within `N` bits.
+///
+/// `N` may be at most the width of a pointer on the target platform.
+#[repr(transparent)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct UInt<const N: u32>(usize);
+
+impl<const N: u32> UInt<N> {
+    /// Number of bits used to represent this value.
+    pub const BITS: u32 = {
+        assert!(N <= PTR_WIDTH, "`UInt<N>` may not be wider than a pointer");
+        N
+    };
 
-        impl fmt::Display for $Uint {
-            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                self.0.fmt(f)
-            }
+    /// The smallest value which can be represented by this type.
+    pub const MIN: Self = UInt(0);
+
+    /// The largest value which can be represented by this type.
+    pub const MAX: Self = UInt(if Self::BITS == 0 {
+        0
+    } else {
+        usize::MAX >> (PTR_WIDTH - Self::BITS)
+    });
+
+    pub const fn new(value: usize) -> Option<Self> {
+        if value > Self::MAX.0 {
+            return None;
         }
+        Some(UInt(value))
+    }
 
-        unsafe impl<S: BitStart> Packable<S> for $Uint {
-            type Packed = SubPack<S, $Uint>;
+    pub const unsafe fn new_unchecked(value: usize) -> Self {
+        UInt(value)
+    }
 
-            const WIDTH: u32 = $width;
+    pub const fn get(&self) -> usize {
+        self.0
+    }
 
-            #[inline]
-            unsafe fn store(self, p: &mut RawPackedBits<S, Self>) {
-                p.write_low_bits(self.0);
-            }
+    /// Checked addition. Returns `None` if the result would not fit in `N`
+    /// bits.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).and_then(Self::new)
+    }
 
-            #[inline]
-            unsafe fn load(p: &RawPackedBits<S, Self>) -> Self {
-                $Uint(p.read_low_bits())
-            }
-        }
+    /// Checked subtraction. Returns `None` if the result would be negative.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(UInt)
+    }
+
+    /// Checked multiplication. Returns `None` if the result would not fit in
+    /// `N` bits.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        self.0.checked_mul(rhs.0).and_then(Self::new)
+    }
+
+    /// Wrapping (modular) addition, wrapping around at `N` bits.
+    pub fn wrapping_add(self, rhs: Self) -> Self {
+        UInt(self.0.wrapping_add(rhs.0) & Self::MAX.0)
+    }
+
+    /// Wrapping (modular) subtraction, wrapping around at `N` bits.
+    pub fn wrapping_sub(self, rhs: Self) -> Self {
+        UInt(self.0.wrapping_sub(rhs.0) & Self::MAX.0)
+    }
+
+    /// Wrapping (modular) multiplication, wrapping around at `N` bits.
+    pub fn wrapping_mul(self, rhs: Self) -> Self {
+        UInt(self.0.wrapping_mul(rhs.0) & Self::MAX.0)
+    }
+
+    /// Saturating addition, clamping the result at [`UInt::MAX`].
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        self.checked_add(rhs).unwrap_or(Self::MAX)
+    }
+
+    /// Saturating subtraction, clamping the result at [`UInt::MIN`].
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        UInt(self.0.saturating_sub(rhs.0))
+    }
+
+    /// Saturating multiplication, clamping the result at [`UInt::MAX`].
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        self.checked_mul(rhs).unwrap_or(Self::MAX)
+    }
+}
+
+impl<const N: u32> fmt::Display for UInt<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<const N: u32> From<UInt<N>> for usize {
+    fn from(value: UInt<N>) -> usize {
+        value.0
+    }
+}
+
+impl<const N: u32> TryFrom<usize> for UInt<N> {
+    type Error = TryFromUIntError;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        Self::new(value).ok_or(TryFromUIntError(()))
+    }
+}
+
+/// The error type returned when a checked conversion into a [`UInt`] fails.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct TryFromUIntError(());
+
+impl fmt::Display for TryFromUIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        "out of range integral type conversion attempted".fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TryFromUIntError {}
+
+unsafe impl<S: BitStart, const N: u32> Packable<S> for UInt<N> {
+    type Packed = SubPack<S, UInt<N>>;
+
+    const WIDTH: u32 = Self::BITS;
+
+    #[inline]
+    unsafe fn store(self, p: &mut RawPackedBits<S, Self>) {
+        p.write_low_bits(self.0);
+    }
+
+    #[inline]
+    unsafe fn load(p: &RawPackedBits<S, Self>) -> Self {
+        UInt(p.read_low_bits())
+    }
+}
+
+unsafe impl<S: BitStart, const N: u32> PlainPackable<S> for UInt<N> {}
+
+impl<S: BitStart, const N: u32> VisitPacked<S> for UInt<N> {
+    fn visit_packed(packed: &SubPack<S, Self>, visitor: &mut dyn PackVisitor) {
+        visitor.visit_bits(BitsKind::Unsigned(N), packed.get().0);
+    }
+}
+
+/// In-place arithmetic on packed integers. These methods only modify the bits
+/// belonging to this value, leaving the rest of the pack untouched.
+impl<S: BitStart, const N: u32> SubPack<S, UInt<N>> {
+    fn update(&mut self, f: impl FnOnce(UInt<N>) -> UInt<N>) {
+        let value = f(UInt(self.__raw.read_low_bits()));
+        unsafe { self.__raw.write_low_bits(value.0) }
+    }
+
+    /// Add `rhs` to the packed value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result does not fit in `N` bits.
+    pub fn add_assign(&mut self, rhs: UInt<N>) {
+        self.update(|v| v.checked_add(rhs).expect("attempt to add with overflow"))
+    }
+
+    /// Subtract `rhs` from the packed value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result would be negative.
+    pub fn sub_assign(&mut self, rhs: UInt<N>) {
+        self.update(|v| {
+            v.checked_sub(rhs)
+                .expect("attempt to subtract with overflow")
+        })
+    }
+
+    /// Add `rhs` to the packed value, wrapping around at `N` bits.
+    pub fn wrapping_add_assign(&mut self, rhs: UInt<N>) {
+        self.update(|v| v.wrapping_add(rhs))
+    }
+
+    /// Subtract `rhs` from the packed value, wrapping around at `N` bits.
+    pub fn wrapping_sub_assign(&mut self, rhs: UInt<N>) {
+        self.update(|v| v.wrapping_sub(rhs))
+    }
+
+    /// Add `rhs` to the packed value, clamping at [`UInt::MAX`].
+    pub fn saturating_add_assign(&mut self, rhs: UInt<N>) {
+        self.update(|v| v.saturating_add(rhs))
+    }
+
+    /// Subtract `rhs` from the packed value, clamping at [`UInt::MIN`].
+    pub fn saturating_sub_assign(&mut self, rhs: UInt<N>) {
+        self.update(|v| v.saturating_sub(rhs))
+    }
+
+    /// Increment the packed value by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is already [`UInt::MAX`].
+    pub fn increment(&mut self) {
+        self.add_assign(UInt(1))
+    }
+
+    /// Decrement the packed value by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is already [`UInt::MIN`].
+    pub fn decrement(&mut self) {
+        self.sub_assign(UInt(1))
+    }
+}
+
+macro_rules! tiny_decl {
+    ($(
+        $Uint:ident: $width:expr;
+    )*) => {$(
+        /// Helper integer value with a specific size.
+        pub type $Uint = UInt<$width>;
     )*}
 }
 
@@ -0,0 +1,37 @@
+//! Descriptions of the bit layout of derived types.
+//!
+//! The `Packed*` helpers for structs expose a `LAYOUT` table describing
+//! where each field is stored, and helpers for enums expose a `VARIANTS` table
+//! describing how each variant is tagged.
+
+/// The position of a single field within a packed value.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct FieldLayout {
+    /// The name of the field, or its index for tuple structs.
+    pub name: &'static str,
+    /// The [`BitStart::START`](crate::bitstart::BitStart::START) of the
+    /// field, i.e. one more than the index of its most significant bit.
+    pub start: u32,
+    /// The number of bits used by the field. Fields which aren't stored have a
+    /// width of `0`.
+    pub width: u32,
+}
+
+/// The encoding of a single variant within a packed enum.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct VariantLayout {
+    /// The name of the variant.
+    pub name: &'static str,
+    /// The value of the tag bits which identify this variant.
+    ///
+    /// For `#[ptrpack(niche)]` enums, unit variants are identified by the
+    /// entire payload holding `tag`, and the data-carrying variant has no tag.
+    pub tag: usize,
+    /// The [`BitStart::START`](crate::bitstart::BitStart::START) of the tag.
+    pub tag_start: u32,
+    /// The number of bits in the tag. Prefix-coded enums use a different
+    /// width for each variant.
+    pub tag_width: u32,
+    /// The position of each field of the variant.
+    pub fields: &'static [FieldLayout],
+}
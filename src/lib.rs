@@ -1,20 +1,34 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+// Unsafe methods document their requirements under a `# Preconditions` heading.
+#![allow(clippy::missing_safety_doc)]
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
 use core::cmp;
 use core::fmt;
+use core::hash;
 use core::marker::PhantomData;
 use core::mem::{self, ManuallyDrop};
 use core::ops::{Deref, DerefMut};
 
 use bitstart::{BitStart, DefaultStart};
 
-pub use ptrpack_macros::Packable;
+#[cfg(feature = "derive")]
+pub use ptrpack_macros::{PackFlags, Packable};
+
+#[macro_use]
+mod macros;
+#[doc(hidden)]
+pub use macros::__private;
+mod support;
+#[doc(hidden)]
+pub use support::__support;
 
 pub mod bitstart;
 pub mod impls;
+pub mod layout;
+pub mod visit;
 
 /// Helper constant value of the width of a pointer in bits.
 const PTR_WIDTH: u32 = usize::leading_zeros(0);
@@ -33,8 +47,8 @@ const fn const_mask(before: u32, after: u32) -> usize {
     // question, and those after the range in question. If either `before` or
     // `after` is `PTR_WIDTH`, these shifts will overflow, wrapping around. This
     // case is caught by the multiplication with `nonempty` below.
-    let not_before = usize::max_value().wrapping_shr(before);
-    let not_after = usize::max_value().wrapping_shl(after);
+    let not_before = usize::MAX.wrapping_shr(before);
+    let not_after = usize::MAX.wrapping_shl(after);
 
     (not_before & not_after) * nonempty
 }
@@ -46,6 +60,15 @@ pub unsafe trait Packable<S: BitStart>: Sized {
     /// Number of bits required to represent this value.
     const WIDTH: u32;
 
+    /// Check whether this value can be stored without losing information.
+    ///
+    /// Most values always fit, but values with fields narrowed using
+    /// `#[ptrpack(bits = N)]` may not. See [`Pack::try_new`].
+    #[inline]
+    fn fits(&self) -> bool {
+        true
+    }
+
     /// Directly store the bits for this value into the given `SubPack`.
     unsafe fn store(self, p: &mut RawPackedBits<S, Self>);
 
@@ -53,14 +76,84 @@ pub unsafe trait Packable<S: BitStart>: Sized {
     unsafe fn load(p: &RawPackedBits<S, Self>) -> Self;
 }
 
+/// # Non-Zero Packable
+///
+/// Marker for [`Packable`] types whose stored bits are never all zero. The
+/// all-zero bit pattern is free to be used as a niche, which allows
+/// `Option<T>` to be packed without any additional bits.
+///
+/// # Preconditions
+///
+/// `store` must never write a value with every bit in the range unset.
+pub unsafe trait NonZeroPackable<S: BitStart>: Packable<S> {}
+
+/// # Plain Packable
+///
+/// Marker for [`Packable`] types which can be loaded from any bit pattern,
+/// such as integers and `bool`. Structs deriving `Packable` with
+/// `#[ptrpack(repr = "...")]` require every field to be plain, as their
+/// packed representation can be created from arbitrary bits.
+///
+/// # Preconditions
+///
+/// `load` must produce a valid value for every possible bit pattern. The
+/// value must be stored in memory as its packed bits, zero-extended (or
+/// sign-extended if `SIGNED` is `true`) to the size of the type, which may be
+/// at most 16 bytes. This allows the bitfield helpers to convert values in
+/// `const` functions.
+pub unsafe trait PlainPackable<S: BitStart>: Packable<S> {
+    /// Whether loaded values are sign-extended from their packed bits.
+    const SIGNED: bool = false;
+}
+
+/// # Niche Packable
+///
+/// Marker for [`Packable`] types whose stored bits, when read using
+/// [`RawPackedBits::read_low_bits`], are never less than `NICHES`. Enums
+/// deriving `Packable` with `#[ptrpack(niche)]` use these values to store
+/// their unit variants.
+///
+/// # Preconditions
+///
+/// `store` must never write a value less than `NICHES`.
+pub unsafe trait NichePackable<S: BitStart>: Packable<S> {
+    /// Number of values which are never written by `store`.
+    const NICHES: usize;
+}
+
+/// Addresses below this value are assumed to never be used by a real
+/// allocation, as the first page of memory is left unmapped by most platforms.
+const MIN_ALLOC_ADDR: usize = 4096;
+
+/// Helper method for computing `NichePackable::NICHES` for pointers to `T`.
+const fn pointer_niches<T>() -> usize {
+    // Dangling pointers to zero-sized types may have an address as low as
+    // `align_of::<T>()`, which is stored as `1`.
+    if mem::size_of::<T>() == 0 {
+        return 1;
+    }
+
+    // The low alignment bits of the pointer are not stored.
+    let niches = MIN_ALLOC_ADDR >> mem::align_of::<T>().trailing_zeros();
+    if niches == 0 {
+        1
+    } else {
+        niches
+    }
+}
+
 /// # Pack
 #[repr(transparent)]
-pub struct Pack<P> {
+pub struct Pack<P: Packable<DefaultStart>> {
     bits: usize,
     _marker: PhantomData<P>,
 }
 
 impl<P: Packable<DefaultStart>> Pack<P> {
+    /// Pack `val`.
+    ///
+    /// Values which do not [`fit`](Packable::fits) trigger a debug assertion,
+    /// and are truncated in release builds.
     pub fn new(val: P) -> Self {
         let mut bits = 0usize;
         unsafe {
@@ -72,13 +165,51 @@ impl<P: Packable<DefaultStart>> Pack<P> {
         }
     }
 
+    /// Pack `val`, returning it back if it does not [`fit`](Packable::fits).
+    pub fn try_new(val: P) -> Result<Self, P> {
+        if val.fits() {
+            Ok(Pack::new(val))
+        } else {
+            Err(val)
+        }
+    }
+
+    /// Create a `Pack` directly from its raw bits.
+    ///
+    /// # Preconditions
+    ///
+    /// `bits` must contain a valid packed `P`, such as one written using
+    /// [`RawPackedBits::write_field`] for every field.
+    pub unsafe fn from_bits(bits: usize) -> Self {
+        Pack {
+            bits,
+            _marker: PhantomData,
+        }
+    }
+
     pub fn into_inner(self) -> P {
-        let bits = self.bits;
-        mem::forget(self);
+        let bits = ManuallyDrop::new(self).bits;
         unsafe { P::load(RawPackedBits::for_bits(&bits)) }
     }
 }
 
+impl<P: Packable<DefaultStart> + Copy> Clone for Pack<P> {
+    fn clone(&self) -> Self {
+        Pack {
+            bits: self.bits,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P: Packable<DefaultStart>> Drop for Pack<P> {
+    fn drop(&mut self) {
+        unsafe {
+            drop(P::load(RawPackedBits::for_bits(&self.bits)));
+        }
+    }
+}
+
 impl<P: Packable<DefaultStart>> Deref for Pack<P> {
     type Target = P::Packed;
 
@@ -170,11 +301,14 @@ where
 
     /// Write new bits for this value from the high bits of `bits`.
     ///
-    /// Used for pointer-like values with unused "low" bits.
+    /// Used for pointer-like values with unused "low" bits. Fields which don't
+    /// start at the top of the pack lose no high bits, as `bits` is only
+    /// shifted right by `BEFORE`, which is at most the number of unused low
+    /// bits.
     ///
     /// # Preconditions
     ///
-    /// Only the least signifigant `P::WIDTH` bits of `bits` may be set.
+    /// Only the most signifigant `P::WIDTH` bits of `bits` may be set.
     pub unsafe fn write_high_bits(&mut self, bits: usize) {
         self.write_unshifted_bits(bits.wrapping_shr(Self::BEFORE));
     }
@@ -246,6 +380,18 @@ where
     }
 }
 
+impl<S, P> fmt::Debug for RawPackedBits<S, P>
+where
+    S: BitStart,
+    P: Packable<S>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("RawPackedBits")
+            .field(&self.read_low_bits())
+            .finish()
+    }
+}
+
 /// # Inner Pack
 pub struct SubPack<S, P> {
     __raw: RawPackedBits<S, P>,
@@ -278,6 +424,20 @@ where
         }
     }
 
+    /// Get the raw bits of the packed value.
+    ///
+    /// This method is not intended for use outside of impls.
+    pub fn as_raw(&self) -> &RawPackedBits<S, P> {
+        &self.__raw
+    }
+
+    /// Mutably get the raw bits of the packed value.
+    ///
+    /// This method is not intended for use outside of impls.
+    pub fn as_raw_mut(&mut self) -> &mut RawPackedBits<S, P> {
+        &mut self.__raw
+    }
+
     /// Cast the reference down to a field.
     ///
     /// This method is not intended for use outside of impls.
@@ -309,42 +469,76 @@ where
     }
 }
 
-impl<S, P> fmt::Debug for SubPack<S, P>
+impl<P> SubPack<DefaultStart, P>
+where
+    P: Packable<DefaultStart>,
+{
+    /// Copy the packed value into a new [`Pack`], without unpacking it.
+    pub fn to_pack(&self) -> Pack<P>
+    where
+        P: Copy,
+    {
+        unsafe { Pack::from_bits(self.__raw.read_unshifted_bits()) }
+    }
+}
+
+impl<S, P> SubPack<S, P>
 where
     S: BitStart,
     P: Packable<S>,
+{
+    /// Borrow a temporary copy of the packed value, which is never dropped,
+    /// so that values which aren't `Copy` can be formatted and compared.
+    fn with_loaded<R>(&self, f: impl FnOnce(&P) -> R) -> R {
+        let value = ManuallyDrop::new(unsafe { P::load(&self.__raw) });
+        f(&value)
+    }
+}
+
+impl<S, P> fmt::Debug for SubPack<S, P>
+where
+    S: BitStart,
+    P: Packable<S> + fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_tuple("SubPack")
-            .field(&self.__raw.read_low_bits())
-            .finish()
+        self.with_loaded(|value| value.fmt(f))
+    }
+}
+
+impl<S, P> hash::Hash for SubPack<S, P>
+where
+    S: BitStart,
+    P: Packable<S> + hash::Hash,
+{
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.with_loaded(|value| value.hash(state))
     }
 }
 
 impl<S, P> cmp::PartialEq for SubPack<S, P>
 where
     S: BitStart,
-    P: Packable<S> + Copy + cmp::PartialEq,
+    P: Packable<S> + cmp::PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
-        self.get().eq(&other.get())
+        self.with_loaded(|value| other.with_loaded(|other| value.eq(other)))
     }
 }
 
 impl<S, P> cmp::PartialEq<P> for SubPack<S, P>
 where
     S: BitStart,
-    P: Packable<S> + Copy + cmp::PartialEq,
+    P: Packable<S> + cmp::PartialEq,
 {
     fn eq(&self, other: &P) -> bool {
-        self.get().eq(other)
+        self.with_loaded(|value| value.eq(other))
     }
 }
 
 impl<S, P> cmp::Eq for SubPack<S, P>
 where
     S: BitStart,
-    P: Packable<S> + Copy + cmp::Eq,
+    P: Packable<S> + cmp::Eq,
 {
 }
 
@@ -0,0 +1,607 @@
+//! Declarative equivalents of `#[derive(Packable)]`, for builds which can't
+//! use procedural macros.
+//!
+//! `macro_rules!` can't build new identifiers, so the name of the `Packed*`
+//! helper, and of any accessors besides the by-value getters, are written out
+//! by the caller.
+
+/// Implementation details of the declarative macros.
+#[doc(hidden)]
+pub mod __private {
+    /// Number of bits required for the tag of an enum with `variants`
+    /// variants, matching `#[derive(Packable)]`.
+    pub const fn tag_width(variants: usize) -> u32 {
+        if variants <= 1 {
+            0
+        } else {
+            usize::BITS - (variants - 1).leading_zeros()
+        }
+    }
+}
+
+/// Declare a struct with named fields along with its [`Packable`] impl and
+/// `Packed*` helper, producing the same layout as `#[derive(Packable)]`.
+///
+/// Each field gets a by-value getter. A `_mut` accessor can be requested by
+/// naming it after the field's type.
+///
+/// ```
+/// use ptrpack::{packable_struct, Pack};
+///
+/// packable_struct! {
+///     #[derive(Copy, Clone)]
+///     pub struct Entry<'a> as PackedEntry {
+///         pub key: &'a u64 => key_mut,
+///         pub dirty: bool,
+///     }
+/// }
+///
+/// let (a, b) = (5, 6);
+/// let mut entry = Pack::new(Entry { key: &a, dirty: false });
+/// entry.key_mut().set(&b);
+/// assert_eq!(*entry.key(), 6);
+/// assert!(!entry.dirty());
+/// ```
+///
+/// [`Packable`]: crate::Packable
+#[macro_export]
+macro_rules! packable_struct {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident $(<$($lt:lifetime),+ $(,)?>)? as $packed:ident {
+            $(
+                $(#[$fattr:meta])*
+                $fvis:vis $field:ident : $fty:ty $(=> $fmut:ident)?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis struct $name $(<$($lt),+>)? {
+            $(
+                $(#[$fattr])*
+                $fvis $field: $fty,
+            )*
+        }
+
+        $crate::packable_struct! {
+            @munch [$vis $name [$($($lt),+)?] $packed]
+            []
+            _PackStart;
+            $([$field : $fty ; ($($fmut)?)])*
+        }
+    };
+
+    // Compute the bitstart of each field in turn.
+    (
+        @munch $header:tt
+        [$($done:tt)*]
+        $start:ty;
+        [$field:ident : $fty:ty ; $fmut:tt]
+        $($rest:tt)*
+    ) => {
+        $crate::packable_struct! {
+            @munch $header
+            [$($done)* [$field : $fty ; $start ; $fmut]]
+            $crate::bitstart::NextStart<$start, $fty>;
+            $($rest)*
+        }
+    };
+
+    (
+        @munch [$vis:vis $name:ident [$($lt:lifetime),*] $packed:ident]
+        [$([$field:ident : $fty:ty ; $start:ty ; ($($fmut:ident)?)])*]
+        $end:ty;
+    ) => {
+        $vis struct $packed<
+            $($lt,)*
+            _PackStart: $crate::bitstart::BitStart = $crate::bitstart::DefaultStart,
+        > {
+            inner: $crate::SubPack<_PackStart, $name<$($lt),*>>,
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> $packed<$($lt,)* _PackStart> {
+            /// The position of each field within the packed value.
+            pub const LAYOUT: &'static [$crate::layout::FieldLayout] = &[$(
+                $crate::layout::FieldLayout {
+                    name: ::core::stringify!($field),
+                    start: <$start as $crate::bitstart::BitStart>::START,
+                    width: <$fty as $crate::Packable<$start>>::WIDTH,
+                },
+            )*];
+
+            $(
+                #[allow(dead_code)]
+                $vis fn $field(&self) -> $fty
+                where
+                    for<'__a> $fty: ::core::marker::Copy,
+                {
+                    unsafe { self.inner.as_field::<$start, $fty>().get() }
+                }
+
+                $(
+                    #[allow(dead_code)]
+                    $vis fn $fmut(&mut self) -> &mut <$fty as $crate::Packable<$start>>::Packed {
+                        unsafe { self.inner.as_field_mut::<$start, $fty>().as_packed_mut() }
+                    }
+                )?
+            )*
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::ops::Deref
+            for $packed<$($lt,)* _PackStart>
+        {
+            type Target = $crate::SubPack<_PackStart, $name<$($lt),*>>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.inner
+            }
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::ops::DerefMut
+            for $packed<$($lt,)* _PackStart>
+        {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.inner
+            }
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::cmp::PartialEq
+            for $packed<$($lt,)* _PackStart>
+        where
+            $(<$fty as $crate::Packable<$start>>::Packed: ::core::cmp::PartialEq,)*
+        {
+            fn eq(&self, other: &Self) -> bool {
+                true $(&& ::core::cmp::PartialEq::eq(
+                    unsafe { self.inner.as_field::<$start, $fty>().as_packed() },
+                    unsafe { other.inner.as_field::<$start, $fty>().as_packed() },
+                ))*
+            }
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::cmp::Eq
+            for $packed<$($lt,)* _PackStart>
+        where
+            $(<$fty as $crate::Packable<$start>>::Packed: ::core::cmp::Eq,)*
+        {
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::hash::Hash
+            for $packed<$($lt,)* _PackStart>
+        where
+            $(<$fty as $crate::Packable<$start>>::Packed: ::core::hash::Hash,)*
+        {
+            fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                $(::core::hash::Hash::hash(
+                    unsafe { self.inner.as_field::<$start, $fty>().as_packed() },
+                    state,
+                );)*
+            }
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::fmt::Debug
+            for $packed<$($lt,)* _PackStart>
+        where
+            $(<$fty as $crate::Packable<$start>>::Packed: ::core::fmt::Debug,)*
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                f.debug_struct(::core::stringify!($name))
+                    $(.field(::core::stringify!($field), unsafe {
+                        self.inner.as_field::<$start, $fty>().as_packed()
+                    }))*
+                    .finish()
+            }
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> $packed<$($lt,)* _PackStart>
+        where
+            $(<$fty as $crate::Packable<$start>>::Packed: $crate::visit::Visitable,)*
+        {
+            /// Report the contents of the packed value to `visitor`.
+            #[allow(dead_code)]
+            $vis fn visit(&self, visitor: &mut impl $crate::visit::PackVisitor) {
+                $crate::visit::Visitable::visit(self, visitor)
+            }
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> $crate::visit::Visitable
+            for $packed<$($lt,)* _PackStart>
+        where
+            $(<$fty as $crate::Packable<$start>>::Packed: $crate::visit::Visitable,)*
+        {
+            fn visit(&self, visitor: &mut dyn $crate::visit::PackVisitor) {
+                $(visitor.visit_field(::core::stringify!($field), unsafe {
+                    self.inner.as_field::<$start, $fty>().as_packed()
+                });)*
+            }
+        }
+
+        unsafe impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> $crate::Packable<_PackStart>
+            for $name<$($lt),*>
+        {
+            type Packed = $packed<$($lt,)* _PackStart>;
+
+            const WIDTH: u32 = <_PackStart as $crate::bitstart::BitStart>::START
+                - <$end as $crate::bitstart::BitStart>::START;
+
+            fn fits(&self) -> bool {
+                true $(&& <$fty as $crate::Packable<$start>>::fits(&self.$field))*
+            }
+
+            unsafe fn store(self, pack: &mut $crate::RawPackedBits<_PackStart, Self>) {
+                let $name { $($field),* } = self;
+                $(pack.write_field::<$start, $fty>($field);)*
+            }
+
+            unsafe fn load(pack: &$crate::RawPackedBits<_PackStart, Self>) -> Self {
+                $name {
+                    $($field: pack.read_field::<$start, $fty>(),)*
+                }
+            }
+        }
+    };
+}
+
+/// Declare an enum whose variants are either units or hold a single value,
+/// along with its [`Packable`] impl and `Packed*` helper, producing the same
+/// layout as `#[derive(Packable)]`.
+///
+/// Each variant may name an `is_` accessor, and variants holding a value may
+/// also name an `as_` accessor borrowing it.
+///
+/// ```
+/// use ptrpack::{packable_enum, Pack};
+///
+/// packable_enum! {
+///     pub enum Slot<'a> as PackedSlot {
+///         Empty => [is_empty],
+///         Full(&'a u64) => [is_full, as_full],
+///     }
+/// }
+///
+/// let value = 3;
+/// let slot = Pack::new(Slot::Full(&value));
+/// assert!(slot.is_full());
+/// assert_eq!(*slot.as_full().unwrap().get(), 3);
+/// ```
+///
+/// [`Packable`]: crate::Packable
+#[macro_export]
+macro_rules! packable_enum {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident $(<$($lt:lifetime),+ $(,)?>)? as $packed:ident {
+            $(
+                $(#[$vattr:meta])*
+                $variant:ident $(($vty:ty))? $(=> [$is:ident $(, $as:ident)?])?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis enum $name $(<$($lt),+>)? {
+            $(
+                $(#[$vattr])*
+                $variant $(($vty))?,
+            )*
+        }
+
+        $crate::packable_enum! {
+            @munch [$vis $name [$($($lt),+)?] $packed [$(($($vty)?))*] [$($($vty,)?)*]]
+            []
+            (0);
+            $([$variant ($($vty)?) ($($is $(, $as)?)?)])*
+        }
+    };
+
+    // Number each variant in turn.
+    (
+        @munch $header:tt
+        [$($done:tt)*]
+        ($($code:tt)*);
+        [$variant:ident $payload:tt $accessors:tt]
+        $($rest:tt)*
+    ) => {
+        $crate::packable_enum! {
+            @munch $header
+            [$($done)* [$variant $payload ($($code)*) $accessors]]
+            ($($code)* + 1);
+            $($rest)*
+        }
+    };
+
+    (
+        @munch [
+            $vis:vis $name:ident [$($lt:lifetime),*] $packed:ident $payloads:tt [$($vty:ty,)*]
+        ]
+        [$([$variant:ident $payload:tt $code:tt ($($is:ident $(, $as:ident)?)?)])*]
+        $count:tt;
+    ) => {
+        $vis struct $packed<
+            $($lt,)*
+            _PackStart: $crate::bitstart::BitStart = $crate::bitstart::DefaultStart,
+        > {
+            inner: $crate::SubPack<_PackStart, $name<$($lt),*>>,
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> $packed<$($lt,)* _PackStart> {
+            fn tag(&self) -> usize {
+                unsafe {
+                    self.inner
+                        .as_raw()
+                        .read_field::<
+                            $crate::packable_enum!(@tag_start $payloads),
+                            $crate::packable_enum!(@tag_ty $count),
+                        >()
+                        .get()
+                }
+            }
+
+            /// The tag and field positions of each variant within the packed
+            /// value.
+            pub const VARIANTS: &'static [$crate::layout::VariantLayout] = &[$(
+                $crate::layout::VariantLayout {
+                    name: ::core::stringify!($variant),
+                    tag: $code,
+                    tag_start: <$crate::packable_enum!(@tag_start $payloads)
+                        as $crate::bitstart::BitStart>::START,
+                    tag_width: <$crate::packable_enum!(@tag_ty $count) as $crate::Packable<
+                        $crate::packable_enum!(@tag_start $payloads),
+                    >>::WIDTH,
+                    fields: $crate::packable_enum!(@fields $payload),
+                },
+            )*];
+
+            $($(
+                #[allow(dead_code)]
+                $vis fn $is(&self) -> bool {
+                    self.tag() == $code
+                }
+
+                $($crate::packable_enum! { @as $vis $is $as $payload })?
+            )?)*
+        }
+
+        const _: () = ::core::assert!(
+            $crate::__private::tag_width$count <= ::core::primitive::usize::BITS,
+            "discriminant is too large to be packed into a pointer-sized integer",
+        );
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::ops::Deref
+            for $packed<$($lt,)* _PackStart>
+        {
+            type Target = $crate::SubPack<_PackStart, $name<$($lt),*>>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.inner
+            }
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::ops::DerefMut
+            for $packed<$($lt,)* _PackStart>
+        {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.inner
+            }
+        }
+
+        // Tags are compared before values, which are only known to be in the
+        // same place if the variants match.
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::cmp::PartialEq
+            for $packed<$($lt,)* _PackStart>
+        where
+            $(<$vty as $crate::Packable<_PackStart>>::Packed: ::core::cmp::PartialEq,)*
+        {
+            fn eq(&self, other: &Self) -> bool {
+                let tag = self.tag();
+                if tag != other.tag() {
+                    return false;
+                }
+                $(
+                    if tag == $code {
+                        return $crate::packable_enum!(@eq self other $payload);
+                    }
+                )*
+                ::core::panic!("invalid packed discriminant")
+            }
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::cmp::Eq
+            for $packed<$($lt,)* _PackStart>
+        where
+            $(<$vty as $crate::Packable<_PackStart>>::Packed: ::core::cmp::Eq,)*
+        {
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::hash::Hash
+            for $packed<$($lt,)* _PackStart>
+        where
+            $(<$vty as $crate::Packable<_PackStart>>::Packed: ::core::hash::Hash,)*
+        {
+            fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                let tag = self.tag();
+                ::core::hash::Hash::hash(&tag, state);
+                $(
+                    if tag == $code {
+                        return $crate::packable_enum!(@hash self state $payload);
+                    }
+                )*
+                ::core::panic!("invalid packed discriminant")
+            }
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> ::core::fmt::Debug
+            for $packed<$($lt,)* _PackStart>
+        where
+            $(<$vty as $crate::Packable<_PackStart>>::Packed: ::core::fmt::Debug,)*
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                let tag = self.tag();
+                $(
+                    if tag == $code {
+                        return $crate::packable_enum!(@debug self f $variant $payload);
+                    }
+                )*
+                ::core::panic!("invalid packed discriminant")
+            }
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> $packed<$($lt,)* _PackStart>
+        where
+            $(<$vty as $crate::Packable<_PackStart>>::Packed: $crate::visit::Visitable,)*
+        {
+            /// Report the contents of the packed value to `visitor`.
+            #[allow(dead_code)]
+            $vis fn visit(&self, visitor: &mut impl $crate::visit::PackVisitor) {
+                $crate::visit::Visitable::visit(self, visitor)
+            }
+        }
+
+        impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> $crate::visit::Visitable
+            for $packed<$($lt,)* _PackStart>
+        where
+            $(<$vty as $crate::Packable<_PackStart>>::Packed: $crate::visit::Visitable,)*
+        {
+            fn visit(&self, visitor: &mut dyn $crate::visit::PackVisitor) {
+                let tag = self.tag();
+                $(
+                    if tag == $code {
+                        visitor.visit_variant(::core::stringify!($variant));
+                        return $crate::packable_enum!(@visit self visitor $payload);
+                    }
+                )*
+                ::core::panic!("invalid packed discriminant")
+            }
+        }
+
+        unsafe impl<$($lt,)* _PackStart: $crate::bitstart::BitStart> $crate::Packable<_PackStart>
+            for $name<$($lt),*>
+        {
+            type Packed = $packed<$($lt,)* _PackStart>;
+
+            const WIDTH: u32 = <_PackStart as $crate::bitstart::BitStart>::START
+                - <$crate::bitstart::NextStart<
+                    $crate::packable_enum!(@tag_start $payloads),
+                    $crate::packable_enum!(@tag_ty $count),
+                > as $crate::bitstart::BitStart>::START;
+
+            fn fits(&self) -> bool {
+                match self {$(
+                    $crate::packable_enum!(@pat $name $variant value $payload) => {
+                        $crate::packable_enum!(@fits value $payload)
+                    }
+                )*}
+            }
+
+            unsafe fn store(self, pack: &mut $crate::RawPackedBits<_PackStart, Self>) {
+                let tag: usize = match self {$(
+                    $crate::packable_enum!(@pat $name $variant value $payload) => {
+                        $crate::packable_enum!(@store pack value $payload);
+                        $code
+                    }
+                )*};
+                pack.write_field::<
+                    $crate::packable_enum!(@tag_start $payloads),
+                    $crate::packable_enum!(@tag_ty $count),
+                >(<$crate::packable_enum!(@tag_ty $count)>::new_unchecked(tag));
+            }
+
+            unsafe fn load(pack: &$crate::RawPackedBits<_PackStart, Self>) -> Self {
+                let tag = pack
+                    .read_field::<
+                        $crate::packable_enum!(@tag_start $payloads),
+                        $crate::packable_enum!(@tag_ty $count),
+                    >()
+                    .get();
+                $(
+                    if tag == $code {
+                        return $crate::packable_enum!(@load pack $name $variant $payload);
+                    }
+                )*
+                ::core::panic!("invalid packed discriminant")
+            }
+        }
+    };
+
+    (@tag_ty ($($count:tt)*)) => {
+        $crate::impls::UInt<{ $crate::__private::tag_width($($count)*) }>
+    };
+
+    // The tag is stored after the longest variant.
+    (@tag_start []) => { _PackStart };
+    (@tag_start [() $($rest:tt)*]) => { $crate::packable_enum!(@tag_start [$($rest)*]) };
+    (@tag_start [($vty:ty) $($rest:tt)*]) => {
+        $crate::bitstart::UnionStart<
+            $crate::bitstart::NextStart<_PackStart, $vty>,
+            $crate::packable_enum!(@tag_start [$($rest)*]),
+        >
+    };
+
+    (@fields ()) => { &[] };
+    (@fields ($vty:ty)) => {
+        &[$crate::layout::FieldLayout {
+            name: "0",
+            start: <_PackStart as $crate::bitstart::BitStart>::START,
+            width: <$vty as $crate::Packable<_PackStart>>::WIDTH,
+        }]
+    };
+
+    (@as $vis:vis $is:ident $as:ident ($vty:ty)) => {
+        #[allow(dead_code)]
+        $vis fn $as(&self) -> ::core::option::Option<&<$vty as $crate::Packable<_PackStart>>::Packed> {
+            if self.$is() {
+                ::core::option::Option::Some(unsafe {
+                    self.inner.as_field::<_PackStart, $vty>().as_packed()
+                })
+            } else {
+                ::core::option::Option::None
+            }
+        }
+    };
+
+    (@eq $this:tt $other:tt ()) => { true };
+    (@eq $this:tt $other:tt ($vty:ty)) => {
+        ::core::cmp::PartialEq::eq(
+            unsafe { $this.inner.as_field::<_PackStart, $vty>().as_packed() },
+            unsafe { $other.inner.as_field::<_PackStart, $vty>().as_packed() },
+        )
+    };
+
+    (@hash $this:tt $state:tt ()) => { () };
+    (@hash $this:tt $state:tt ($vty:ty)) => {
+        ::core::hash::Hash::hash(
+            unsafe { $this.inner.as_field::<_PackStart, $vty>().as_packed() },
+            $state,
+        )
+    };
+
+    (@debug $this:tt $f:tt $variant:ident ()) => {
+        $f.write_str(::core::stringify!($variant))
+    };
+    (@debug $this:tt $f:tt $variant:ident ($vty:ty)) => {
+        $f.debug_tuple(::core::stringify!($variant))
+            .field(unsafe { $this.inner.as_field::<_PackStart, $vty>().as_packed() })
+            .finish()
+    };
+
+    (@visit $this:tt $visitor:tt ()) => { () };
+    (@visit $this:tt $visitor:tt ($vty:ty)) => {
+        $visitor.visit_field("0", unsafe { $this.inner.as_field::<_PackStart, $vty>().as_packed() })
+    };
+
+    (@pat $name:ident $variant:ident $value:ident ()) => { $name::$variant };
+    (@pat $name:ident $variant:ident $value:ident ($vty:ty)) => { $name::$variant($value) };
+
+    (@fits $value:ident ()) => { true };
+    (@fits $value:ident ($vty:ty)) => {
+        <$vty as $crate::Packable<_PackStart>>::fits($value)
+    };
+
+    (@store $pack:ident $value:ident ()) => {};
+    (@store $pack:ident $value:ident ($vty:ty)) => {
+        $pack.write_field::<_PackStart, $vty>($value)
+    };
+
+    (@load $pack:ident $name:ident $variant:ident ()) => { $name::$variant };
+    (@load $pack:ident $name:ident $variant:ident ($vty:ty)) => {
+        $name::$variant($pack.read_field::<_PackStart, $vty>())
+    };
+}
@@ -0,0 +1,141 @@
+//! Runtime support for the code generated by `#[derive(Packable)]`.
+
+/// Implementation details of the derive macros.
+#[doc(hidden)]
+pub mod __support {
+    use crate::bitstart::{BitStart, PrefixTag};
+    use crate::{Packable, PlainPackable, RawPackedBits};
+
+    use core::mem;
+
+    /// Assign tags to the variants of a `#[ptrpack(tag = "prefix")]` enum
+    /// whose payloads are `widths` bits wide, minimizing the combined width
+    /// of the widest tag and payload.
+    pub const fn prefix_tags<const N: usize>(widths: [u32; N]) -> [PrefixTag; N] {
+        // Repeatedly merge the two narrowest subtrees as in Huffman coding,
+        // except that a merged subtree is one bit wider than its widest half,
+        // rather than the sum of both.
+        let mut root = [0; N];
+        let mut active = [true; N];
+        let mut weights = widths;
+        let mut lens = [0u32; N];
+        let mut idx = 0;
+        while idx < N {
+            root[idx] = idx;
+            idx += 1;
+        }
+
+        let mut merges = 1;
+        while merges < N {
+            let (mut a, mut b) = (N, N);
+            idx = 0;
+            while idx < N {
+                if active[idx] {
+                    if a == N || weights[idx] < weights[a] {
+                        b = a;
+                        a = idx;
+                    } else if b == N || weights[idx] < weights[b] {
+                        b = idx;
+                    }
+                }
+                idx += 1;
+            }
+
+            weights[a] = if weights[a] > weights[b] {
+                weights[a]
+            } else {
+                weights[b]
+            } + 1;
+            active[b] = false;
+            idx = 0;
+            while idx < N {
+                if root[idx] == a || root[idx] == b {
+                    root[idx] = a;
+                    lens[idx] += 1;
+                }
+                idx += 1;
+            }
+            merges += 1;
+        }
+
+        // Assign canonical codes, ordered by length and then declaration.
+        let mut tags = [PrefixTag { code: 0, width: 0 }; N];
+        let mut code = 0;
+        let mut prev = 0;
+        let mut len = 0;
+        while len < N as u32 {
+            idx = 0;
+            while idx < N {
+                if lens[idx] == len {
+                    code <<= len - prev;
+                    prev = len;
+                    tags[idx] = PrefixTag { code, width: len };
+                    code += 1;
+                }
+                idx += 1;
+            }
+            len += 1;
+        }
+        tags
+    }
+
+    /// Storage for converting [`PlainPackable`] values to and from their
+    /// bits in `const` functions, which can't call trait methods.
+    union PlainBits<T: Copy> {
+        bits: u128,
+        value: T,
+    }
+
+    /// Shift placing the low bytes of a `u128` where `PlainBits::value`
+    /// overlaps it.
+    const fn plain_shift<T>() -> u32 {
+        let size = mem::size_of::<T>() as u32;
+        assert!(size <= 16, "plain values may be at most 16 bytes");
+        if cfg!(target_endian = "big") && size > 0 {
+            128 - 8 * size
+        } else {
+            0
+        }
+    }
+
+    /// Load a plain value from `bits` in a `const` function, for the
+    /// helpers generated by `#[ptrpack(repr = "...")]`.
+    ///
+    /// # Safety
+    ///
+    /// `bits` must be the bits of a packed value with `T` at `S`.
+    pub const unsafe fn read_plain<S, T>(bits: usize) -> T
+    where
+        S: BitStart,
+        T: PlainPackable<S> + Copy,
+    {
+        let low = (bits & RawPackedBits::<S, T>::MASK).wrapping_shr(RawPackedBits::<S, T>::AFTER);
+        let mut low = low as u128;
+        let width = <T as Packable<S>>::WIDTH;
+        if T::SIGNED && width > 0 {
+            low = ((low << (128 - width)) as i128 >> (128 - width)) as u128;
+        }
+        PlainBits {
+            bits: low << plain_shift::<T>(),
+        }
+        .value
+    }
+
+    /// Store a plain value into `bits` in a `const` function, returning the
+    /// updated bits.
+    ///
+    /// # Safety
+    ///
+    /// `value` must fit in the bits reserved for `T` at `S`.
+    pub const unsafe fn write_plain<S, T>(bits: usize, value: T) -> usize
+    where
+        S: BitStart,
+        T: PlainPackable<S> + Copy,
+    {
+        let mut plain = PlainBits { bits: 0 };
+        plain.value = value;
+        let low = (plain.bits >> plain_shift::<T>()) as usize;
+        (bits & RawPackedBits::<S, T>::CLEAR_MASK)
+            | (low.wrapping_shl(RawPackedBits::<S, T>::AFTER) & RawPackedBits::<S, T>::MASK)
+    }
+}
@@ -0,0 +1,103 @@
+//! Walking the fields of packed values without knowing their shape.
+//!
+//! Derived `Packed*` helpers and `Packed*Repr` bitfields, and the packed views
+//! of primitive types, implement [`Visitable`], reporting their contents to a
+//! [`PackVisitor`].
+//!
+//! ```
+//! use ptrpack::visit::{BitsKind, PackVisitor, Visitable};
+//! use ptrpack::{Pack, Packable};
+//!
+//! #[derive(Packable)]
+//! struct Point {
+//!     x: u8,
+//!     y: bool,
+//! }
+//!
+//! #[derive(Default)]
+//! struct Printer(String);
+//!
+//! impl PackVisitor for Printer {
+//!     fn visit_field(&mut self, name: &'static str, value: &dyn Visitable) {
+//!         self.0 += &format!("{}: ", name);
+//!         value.visit(self);
+//!         self.0 += "; ";
+//!     }
+//!
+//!     fn visit_variant(&mut self, name: &'static str) {
+//!         self.0 += name;
+//!     }
+//!
+//!     fn visit_bits(&mut self, _kind: BitsKind, value: usize) {
+//!         self.0 += &value.to_string();
+//!     }
+//! }
+//!
+//! let mut printer = Printer::default();
+//! Pack::new(Point { x: 3, y: true }).visit(&mut printer);
+//! assert_eq!(printer.0, "x: 3; y: 1; ");
+//! ```
+//!
+//! Boxes are only reported as [`BitsKind::Box`] with the address they point
+//! to, so visitors can't walk into the values they own.
+
+use crate::bitstart::BitStart;
+use crate::{Packable, SubPack};
+
+/// The kind of a primitive value reported to [`PackVisitor::visit_bits`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
+pub enum BitsKind {
+    /// A `bool`, with a value of `0` or `1`.
+    Bool,
+    /// An unsigned integer with the given width in bits.
+    Unsigned(u32),
+    /// A signed integer with the given width in bits. The value is
+    /// sign-extended to a `usize`.
+    Signed(u32),
+    /// A `char`, with its scalar value.
+    Char,
+    /// A reference, with the address it points to.
+    Ref,
+    /// A `Box`, with the address it points to.
+    Box,
+}
+
+/// Receives the contents of a packed value from [`Visitable::visit`].
+pub trait PackVisitor {
+    /// Called for each stored field of a struct or enum variant, in
+    /// declaration order. Tuple fields are named by their index.
+    ///
+    /// Visitors which want to walk nested values call `value.visit(self)`.
+    fn visit_field(&mut self, name: &'static str, value: &dyn Visitable);
+
+    /// Called with the name of the current variant of an enum, before any of
+    /// its fields.
+    fn visit_variant(&mut self, name: &'static str);
+
+    /// Called for primitive values.
+    fn visit_bits(&mut self, kind: BitsKind, value: usize);
+}
+
+/// A packed value which can report its contents to a [`PackVisitor`].
+pub trait Visitable {
+    /// Report the contents of this value to `visitor`.
+    fn visit(&self, visitor: &mut dyn PackVisitor);
+}
+
+/// Types whose packed view is a [`SubPack`], and which can report its
+/// contents to a [`PackVisitor`].
+///
+/// [`Visitable`] is implemented for `SubPack<S, P>` whenever `P` implements
+/// this trait. It is implemented by `#[derive(Packable)]` for types with
+/// `#[ptrpack(transparent)]`.
+pub trait VisitPacked<S: BitStart>: Packable<S> {
+    /// Report the contents of `packed` to `visitor`.
+    fn visit_packed(packed: &SubPack<S, Self>, visitor: &mut dyn PackVisitor);
+}
+
+impl<S: BitStart, P: VisitPacked<S>> Visitable for SubPack<S, P> {
+    fn visit(&self, visitor: &mut dyn PackVisitor) {
+        P::visit_packed(self, visitor)
+    }
+}
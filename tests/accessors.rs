@@ -0,0 +1,86 @@
+use ptrpack::{Pack, Packable};
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Entry {
+    #[ptrpack(bits = 12)]
+    offset: u16,
+    dirty: bool,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Pair(u8, i8);
+
+// `Box` isn't `Copy`, so there's no by-value getter for `value`.
+#[derive(Packable, Debug, Eq, PartialEq)]
+struct Owned {
+    value: Box<u64>,
+    flag: bool,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(legacy_setters)]
+struct Legacy {
+    count: u8,
+}
+
+#[test]
+fn test_accessors() {
+    let mut packed = Pack::new(Entry {
+        offset: 100,
+        dirty: false,
+    });
+    assert_eq!(packed.offset(), 100);
+    assert!(!packed.dirty());
+
+    packed.set_offset(4000);
+    assert!(!packed.replace_dirty(true));
+    assert_eq!(
+        packed.get(),
+        Entry {
+            offset: 4000,
+            dirty: true,
+        }
+    );
+
+    packed.dirty_mut().set(false);
+    assert!(!packed.get_dirty().get());
+    assert!(!packed.dirty());
+    assert_eq!(packed.replace_offset(7), 4000);
+    assert_eq!(packed.offset(), 7);
+}
+
+#[test]
+fn test_tuple_accessors() {
+    let mut packed = Pack::new(Pair(1, -1));
+    assert_eq!((packed._0(), packed._1()), (1, -1));
+    packed.set_0(2);
+    assert_eq!(packed.replace_1(-2), -1);
+    assert_eq!(packed.get(), Pair(2, -2));
+}
+
+#[test]
+fn test_non_copy_accessors() {
+    let mut packed = Pack::new(Owned {
+        value: Box::new(5),
+        flag: true,
+    });
+    assert!(packed.flag());
+    assert_eq!(packed.replace_value(Box::new(6)), Box::new(5));
+    packed.set_value(Box::new(7));
+    assert_eq!(
+        packed.into_inner(),
+        Owned {
+            value: Box::new(7),
+            flag: true,
+        }
+    );
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_legacy_accessors() {
+    let mut packed = Pack::new(Legacy { count: 3 });
+    packed.set_count().set(4);
+    assert_eq!(packed.get_count().get(), 4);
+    assert_eq!(packed.count(), 4);
+}
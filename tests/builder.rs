@@ -0,0 +1,135 @@
+use std::rc::Rc;
+
+use ptrpack::{Pack, Packable};
+
+#[repr(align(64))]
+#[derive(Debug, Eq, PartialEq)]
+struct Slot(u32);
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Cell<'a> {
+    value: &'a Slot,
+    #[ptrpack(bits = 4)]
+    generation: u8,
+    marked: bool,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq)]
+struct Owned {
+    value: Box<u64>,
+    flag: bool,
+}
+
+#[test]
+fn test_with() {
+    let (a, b) = (Slot(1), Slot(2));
+    let cell = Pack::new(Cell {
+        value: &a,
+        generation: 3,
+        marked: false,
+    });
+
+    let updated = cell.clone().with_marked(true).with_value(&b);
+    assert_eq!(
+        updated.get(),
+        Cell {
+            value: &b,
+            generation: 3,
+            marked: true,
+        }
+    );
+
+    // The original pack is unchanged.
+    assert_eq!(cell.value(), &a);
+    assert!(!cell.marked());
+    assert_eq!(cell.clone().with_generation(15).generation(), 15);
+}
+
+// Counts how many packed copies of an `Rc` are alive.
+struct Counted(#[allow(dead_code)] Rc<u32>);
+
+#[derive(Packable)]
+struct Shared {
+    value: Box<Counted>,
+    flag: bool,
+}
+
+#[test]
+fn test_with_drops_replaced() {
+    let (a, b) = (Rc::new(1), Rc::new(2));
+    let shared = Pack::new(Shared {
+        value: Box::new(Counted(a.clone())),
+        flag: false,
+    });
+
+    let shared = shared
+        .with_value(Box::new(Counted(b.clone())))
+        .with_flag(true);
+    assert_eq!(Rc::strong_count(&a), 1);
+    assert_eq!(Rc::strong_count(&b), 2);
+    assert!(shared.flag());
+
+    drop(shared);
+    assert_eq!(Rc::strong_count(&b), 1);
+}
+
+#[test]
+fn test_builder() {
+    let a = Slot(1);
+    let cell = PackedCellBuilder::new()
+        .marked(true)
+        .value(&a)
+        .generation(9)
+        .build();
+    assert_eq!(
+        cell.get(),
+        Cell {
+            value: &a,
+            generation: 9,
+            marked: true,
+        }
+    );
+
+    let owned = PackedOwnedBuilder::default()
+        .value(Box::new(5))
+        .flag(true)
+        .build();
+    assert_eq!(
+        owned.into_inner(),
+        Owned {
+            value: Box::new(5),
+            flag: true,
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "field `generation` was not set")]
+fn test_builder_unset_field() {
+    let a = Slot(1);
+    PackedCellBuilder::new().value(&a).marked(false).build();
+}
+
+#[test]
+fn test_builder_drops_values() {
+    let (a, b) = (Rc::new(1), Rc::new(2));
+
+    // Overwritten values are dropped immediately.
+    let builder = PackedSharedBuilder::new()
+        .value(Box::new(Counted(a.clone())))
+        .value(Box::new(Counted(b.clone())));
+    assert_eq!(Rc::strong_count(&a), 1);
+    assert_eq!(Rc::strong_count(&b), 2);
+
+    // Values in a builder which is never built are dropped with it.
+    drop(builder);
+    assert_eq!(Rc::strong_count(&b), 1);
+
+    let shared = PackedSharedBuilder::new()
+        .value(Box::new(Counted(a.clone())))
+        .flag(true)
+        .build();
+    assert_eq!(Rc::strong_count(&a), 2);
+    drop(shared);
+    assert_eq!(Rc::strong_count(&a), 1);
+}
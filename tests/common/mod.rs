@@ -0,0 +1,22 @@
+//! Helpers shared between the integration tests.
+
+// Each test crate includes this module, and not all of them use every helper.
+#![allow(dead_code)]
+
+use ptrpack::bitstart::DefaultStart;
+use ptrpack::{Pack, Packable};
+
+/// Width of `P` when packed at the top of a pointer.
+pub fn width<P: Packable<DefaultStart>>() -> u32 {
+    P::WIDTH
+}
+
+/// The underlying bits of `pack`.
+pub fn raw_bits<P: Packable<DefaultStart>>(pack: &Pack<P>) -> usize {
+    unsafe { *(pack as *const Pack<P> as *const usize) }
+}
+
+/// The bits `value` packs into, shifted down to the low bits.
+pub fn bits<P: Packable<DefaultStart>>(value: P) -> usize {
+    raw_bits(&Pack::new(value)) >> (usize::BITS - P::WIDTH)
+}
@@ -0,0 +1,93 @@
+use ptrpack::{Pack, Packable};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+#[derive(Packable, Debug, PartialEq, Eq, Hash)]
+struct Node {
+    value: Box<u64>,
+    flag: bool,
+}
+
+#[derive(Packable, Debug, PartialEq, Eq, Hash)]
+enum Expr {
+    Lit(u32),
+    Neg(Box<Expr>),
+}
+
+#[derive(Packable, Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[ptrpack(transparent)]
+struct Id(#[ptrpack(bits = 12)] u16);
+
+#[derive(Packable, Debug, PartialEq, Eq, Hash)]
+#[ptrpack(transparent)]
+struct Handle(Box<u32>);
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn neg(expr: Expr) -> Expr {
+    Expr::Neg(Box::new(expr))
+}
+
+#[test]
+fn test_struct() {
+    let a = Pack::new(Node {
+        value: Box::new(5),
+        flag: true,
+    });
+    let b = Pack::new(Node {
+        value: Box::new(5),
+        flag: true,
+    });
+    let c = Pack::new(Node {
+        value: Box::new(5),
+        flag: false,
+    });
+
+    // Boxes are compared by value, not by address.
+    assert_eq!(*a, *b);
+    assert_ne!(*a, *c);
+    assert_eq!(hash(&*a), hash(&*b));
+    assert_eq!(format!("{:?}", *a), "Node { value: 5, flag: true }");
+}
+
+#[test]
+fn test_enum() {
+    let a = Pack::new(neg(neg(Expr::Lit(1))));
+    let b = Pack::new(neg(neg(Expr::Lit(1))));
+    let c = Pack::new(neg(Expr::Lit(1)));
+    let d = Pack::new(Expr::Lit(1));
+
+    assert_eq!(*a, *b);
+    assert_ne!(*a, *c);
+    assert_ne!(*c, *d);
+    assert_eq!(hash(&*a), hash(&*b));
+    assert_eq!(format!("{:?}", *a), "Neg(Neg(Lit(1)))");
+    assert_eq!(format!("{:?}", *d), "Lit(1)");
+}
+
+#[test]
+fn test_transparent() {
+    // Transparent types are viewed through `SubPack`, which formats and
+    // compares the loaded value.
+    let id = Pack::new(Id(42));
+    assert_eq!(*id, *Pack::new(Id(42)));
+    assert_eq!(hash(&*id), hash(&Id(42)));
+    assert_eq!(format!("{:?}", *id), "Id(42)");
+
+    // Values which aren't `Copy` are borrowed in place.
+    let a = Pack::new(Handle(Box::new(3)));
+    let b = Pack::new(Handle(Box::new(3)));
+    assert_eq!(*a, *b);
+    assert_ne!(*a, *Pack::new(Handle(Box::new(4))));
+    assert_eq!(hash(&*a), hash(&Handle(Box::new(3))));
+    assert_eq!(format!("{:?}", *a), "Handle(3)");
+    assert_eq!(
+        format!("{:?}", a.as_raw()),
+        format!("RawPackedBits({})", a.as_raw().read_low_bits())
+    );
+    assert_eq!(*a.into_inner().0, 3);
+}
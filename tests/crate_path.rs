@@ -0,0 +1,30 @@
+use ::ptrpack::bitstart::DefaultStart;
+use ::ptrpack::{Pack, Packable};
+
+// A local module named `ptrpack` must not confuse the generated code.
+#[allow(dead_code)]
+mod ptrpack {}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Local {
+    a: u8,
+    b: bool,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(tag = "high")]
+enum LocalEnum {
+    A(u8),
+    B,
+}
+
+#[test]
+fn test_local_ptrpack_module() {
+    assert_eq!(<Local as Packable<DefaultStart>>::WIDTH, 9);
+    let local = Local { a: 3, b: true };
+    assert_eq!(Pack::new(local).get(), local);
+
+    for &value in &[LocalEnum::A(7), LocalEnum::B] {
+        assert_eq!(Pack::new(value).get(), value);
+    }
+}
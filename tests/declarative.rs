@@ -0,0 +1,193 @@
+use ptrpack::visit::{BitsKind, PackVisitor, Visitable};
+use ptrpack::{packable_enum, packable_struct, Pack};
+
+mod common;
+
+#[repr(align(512))]
+#[derive(Debug, Eq, PartialEq)]
+struct Node(u32);
+
+packable_struct! {
+    #[derive(Debug, Eq, PartialEq, Copy, Clone)]
+    struct Plain<'a> as PackedPlain {
+        node: &'a Node => node_mut,
+        flag: bool,
+        count: u8,
+    }
+}
+
+packable_enum! {
+    #[derive(Debug, Eq, PartialEq, Copy, Clone)]
+    enum PlainEnum<'a> as PackedPlainEnum {
+        Node(&'a Node) => [is_node, as_node],
+        Flag(bool),
+        Empty => [is_empty],
+    }
+}
+
+#[test]
+fn test_struct() {
+    let (a, b) = (Node(1), Node(2));
+    let mut pack = Pack::new(Plain {
+        node: &a,
+        flag: true,
+        count: 7,
+    });
+    assert_eq!(pack.node(), &a);
+    assert!(pack.flag());
+    assert_eq!(pack.count(), 7);
+
+    assert_eq!(
+        format!("{:?}", *pack),
+        "Plain { node: Node(1), flag: true, count: 7 }"
+    );
+
+    pack.node_mut().set(&b);
+    assert_eq!(
+        pack.get(),
+        Plain {
+            node: &b,
+            flag: true,
+            count: 7,
+        }
+    );
+}
+
+#[test]
+fn test_enum() {
+    let node = Node(4);
+    let pack = Pack::new(PlainEnum::Node(&node));
+    assert!(pack.is_node());
+    assert!(!pack.is_empty());
+    assert_eq!(pack.as_node().unwrap().get(), &node);
+
+    let pack = Pack::new(PlainEnum::Empty);
+    assert!(pack.is_empty());
+    assert!(pack.as_node().is_none());
+
+    for value in [
+        PlainEnum::Flag(true),
+        PlainEnum::Flag(false),
+        PlainEnum::Empty,
+    ] {
+        assert_eq!(Pack::new(value).get(), value);
+    }
+
+    assert_eq!(
+        *Pack::new(PlainEnum::Flag(true)),
+        *Pack::new(PlainEnum::Flag(true))
+    );
+    assert_ne!(
+        *Pack::new(PlainEnum::Flag(true)),
+        *Pack::new(PlainEnum::Empty)
+    );
+    assert_eq!(
+        format!("{:?}", *Pack::new(PlainEnum::Flag(true))),
+        "Flag(true)"
+    );
+}
+
+// Compare against `#[derive(Packable)]`, when it's available. The rest of this
+// file exercises the declarative macros alone, so that it also runs with the
+// `derive` feature disabled.
+#[cfg(feature = "derive")]
+mod derive {
+    use super::*;
+    use ptrpack::bitstart::DefaultStart;
+    use ptrpack::Packable;
+
+    use crate::common::raw_bits;
+
+    #[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+    struct Derived<'a> {
+        node: &'a Node,
+        flag: bool,
+        count: u8,
+    }
+
+    #[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+    enum DerivedEnum<'a> {
+        Node(&'a Node),
+        Flag(bool),
+        Empty,
+    }
+
+    #[test]
+    fn test_struct_matches_derive() {
+        assert_eq!(
+            PackedPlain::<DefaultStart>::LAYOUT,
+            PackedDerived::<DefaultStart>::LAYOUT
+        );
+        assert_eq!(
+            <Plain as Packable<DefaultStart>>::WIDTH,
+            <Derived as Packable<DefaultStart>>::WIDTH,
+        );
+
+        let node = Node(3);
+        assert_eq!(
+            raw_bits(&Pack::new(Plain {
+                node: &node,
+                flag: true,
+                count: 0x5a,
+            })),
+            raw_bits(&Pack::new(Derived {
+                node: &node,
+                flag: true,
+                count: 0x5a,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_enum_matches_derive() {
+        assert_eq!(
+            PackedPlainEnum::<DefaultStart>::VARIANTS,
+            PackedDerivedEnum::<DefaultStart>::VARIANTS
+        );
+
+        let node = Node(5);
+        assert_eq!(
+            raw_bits(&Pack::new(PlainEnum::Node(&node))),
+            raw_bits(&Pack::new(DerivedEnum::Node(&node)))
+        );
+        assert_eq!(
+            raw_bits(&Pack::new(PlainEnum::Flag(true))),
+            raw_bits(&Pack::new(DerivedEnum::Flag(true)))
+        );
+        assert_eq!(
+            raw_bits(&Pack::new(PlainEnum::Empty)),
+            raw_bits(&Pack::new(DerivedEnum::Empty))
+        );
+    }
+}
+
+#[derive(Default)]
+struct Names(Vec<&'static str>);
+
+impl PackVisitor for Names {
+    fn visit_field(&mut self, name: &'static str, value: &dyn Visitable) {
+        self.0.push(name);
+        value.visit(self);
+    }
+
+    fn visit_variant(&mut self, name: &'static str) {
+        self.0.push(name);
+    }
+
+    fn visit_bits(&mut self, _kind: BitsKind, _value: usize) {}
+}
+
+#[test]
+fn test_visit() {
+    let node = Node(6);
+    let mut names = Names::default();
+    Pack::new(Plain {
+        node: &node,
+        flag: false,
+        count: 0,
+    })
+    .visit(&mut names);
+    Pack::new(PlainEnum::Flag(true)).visit(&mut names);
+    Pack::new(PlainEnum::Empty).visit(&mut names);
+    assert_eq!(names.0, ["node", "flag", "count", "Flag", "0", "Empty"]);
+}
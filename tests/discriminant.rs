@@ -0,0 +1,78 @@
+use ptrpack::{Pack, Packable};
+
+mod common;
+use common::{bits, width};
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[repr(u8)]
+enum Opcode {
+    Nop = 0,
+    Load = 5,
+    Store,
+    Jump = 0x20,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[repr(i8)]
+enum Sign {
+    Negative = -1,
+    Zero = 0,
+    Positive = 1,
+}
+
+#[cfg(target_pointer_width = "64")]
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[repr(isize)]
+enum Offset {
+    Back = -1,
+    Stay,
+    Forward,
+}
+
+#[test]
+fn test_explicit_discriminants() {
+    assert_eq!(width::<Opcode>(), 6);
+    for &op in &[Opcode::Nop, Opcode::Load, Opcode::Store, Opcode::Jump] {
+        assert_eq!(bits(op), op as usize);
+        assert_eq!(Pack::new(op).get(), op);
+    }
+}
+
+#[test]
+fn test_signed_repr_discriminants() {
+    assert_eq!(width::<Sign>(), 8);
+    for &sign in &[Sign::Negative, Sign::Zero, Sign::Positive] {
+        assert_eq!(bits(sign), sign as u8 as usize);
+        assert_eq!(Pack::new(sign).get(), sign);
+    }
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn test_isize_discriminants() {
+    assert_eq!(width::<Offset>(), 64);
+    for &offset in &[Offset::Back, Offset::Stay, Offset::Forward] {
+        assert_eq!(bits(offset), offset as usize);
+        assert_eq!(Pack::new(offset).get(), offset);
+    }
+}
+
+// Discriminants wider than 32 bits are only checked against the target's
+// pointer width, not the width of the machine running the derive.
+#[cfg(target_pointer_width = "64")]
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[repr(u64)]
+enum Wide {
+    Small = 1,
+    Large = 0x1_0000_0000,
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn test_wide_discriminants() {
+    assert_eq!(width::<Wide>(), 33);
+    for &wide in &[Wide::Small, Wide::Large] {
+        assert_eq!(bits(wide), wide as usize);
+        assert_eq!(Pack::new(wide).get(), wide);
+    }
+}
@@ -9,6 +9,7 @@ struct Something<'a, T> {
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_something() {
     let apple = &15u32;
     let pear = true;
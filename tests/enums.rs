@@ -0,0 +1,184 @@
+use std::rc::Rc;
+
+use ptrpack::bitstart::DefaultStart;
+use ptrpack::impls::{U3, U4};
+use ptrpack::{Pack, Packable};
+
+#[derive(Debug, Eq, PartialEq)]
+#[repr(align(64))]
+pub struct Expr(u32);
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Node<'a> {
+    Binary { lhs: &'a Expr, op: U4 },
+    Unary(&'a Expr, U3, bool),
+    Leaf {},
+}
+
+#[test]
+fn test_struct_and_tuple_variants() {
+    // The widest variant is `Binary`, which needs 58 + 4 bits on 64-bit
+    // targets, with 2 bits for the discriminant.
+    assert_eq!(
+        <Node as Packable<DefaultStart>>::WIDTH,
+        <&Expr as Packable<DefaultStart>>::WIDTH + 4 + 2
+    );
+
+    let expr = Expr(10);
+    let nodes = [
+        Node::Binary {
+            lhs: &expr,
+            op: U4::new(9).unwrap(),
+        },
+        Node::Unary(&expr, U3::new(5).unwrap(), true),
+        Node::Leaf {},
+    ];
+    for &node in &nodes {
+        assert_eq!(Pack::new(node).get(), node);
+    }
+}
+
+#[derive(Packable)]
+pub enum Owned {
+    Single(Box<Rc<u32>>),
+    Pair { value: Box<Rc<u32>>, flag: bool },
+    Nothing,
+}
+
+#[test]
+fn test_owned_variants() {
+    let rc = Rc::new(5);
+
+    let single = Pack::new(Owned::Single(Box::new(rc.clone())));
+    let pair = Pack::new(Owned::Pair {
+        value: Box::new(rc.clone()),
+        flag: true,
+    });
+    assert_eq!(Rc::strong_count(&rc), 3);
+
+    match single.into_inner() {
+        Owned::Single(value) => assert_eq!(**value, 5),
+        _ => panic!("expected `Single`"),
+    }
+    match pair.into_inner() {
+        Owned::Pair { value, flag } => {
+            assert_eq!(**value, 5);
+            assert!(flag);
+        }
+        _ => panic!("expected `Pair`"),
+    }
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    assert!(matches!(
+        Pack::new(Owned::Nothing).into_inner(),
+        Owned::Nothing
+    ));
+}
+
+#[derive(Packable)]
+pub enum Dropped {
+    Single(Box<Rc<u32>>),
+    Pair { value: Box<Rc<u32>>, flag: bool },
+    Tagged(Box<Rc<u32>>, bool),
+    Nothing,
+}
+
+#[test]
+fn test_drop_variants() {
+    let rc = Rc::new(5);
+    let packs = vec![
+        Pack::new(Dropped::Single(Box::new(rc.clone()))),
+        Pack::new(Dropped::Pair {
+            value: Box::new(rc.clone()),
+            flag: false,
+        }),
+        Pack::new(Dropped::Tagged(Box::new(rc.clone()), true)),
+        Pack::new(Dropped::Nothing),
+    ];
+    assert_eq!(Rc::strong_count(&rc), 4);
+
+    drop(packs);
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
+
+#[test]
+fn test_variant_accessors() {
+    let expr = Expr(3);
+    let other = Expr(4);
+    let mut packed = Pack::new(Node::Unary(&expr, U3::new(2).unwrap(), false));
+
+    assert!(packed.is_unary());
+    assert!(!packed.is_binary());
+    assert!(!packed.is_leaf());
+    assert!(packed.as_binary().is_none());
+
+    let (operand, op, flag) = packed.as_unary().unwrap();
+    assert_eq!(operand.get(), &expr);
+    assert_eq!(op.get().get(), 2);
+    assert!(!flag.get());
+
+    packed.set(Node::Binary {
+        lhs: &expr,
+        op: U4::new(1).unwrap(),
+    });
+    let (lhs, op) = packed.as_binary().unwrap();
+    assert_eq!(lhs.get(), &expr);
+    assert_eq!(op.get().get(), 1);
+
+    assert!(packed.with_unary_mut(|_, _, _| ()).is_none());
+    let prev = packed.with_binary_mut(|lhs, op| {
+        *lhs = &other;
+        std::mem::replace(op, U4::new(9).unwrap())
+    });
+    assert_eq!(prev, Some(U4::new(1).unwrap()));
+    assert_eq!(
+        packed.get(),
+        Node::Binary {
+            lhs: &other,
+            op: U4::new(9).unwrap(),
+        }
+    );
+}
+
+#[test]
+fn test_single_field_accessor_mut() {
+    let mut packed = Pack::new(Owned::Single(Box::new(Rc::new(1))));
+    assert!(packed.as_pair().is_none());
+
+    let old = packed
+        .as_single_mut()
+        .unwrap()
+        .replace(Box::new(Rc::new(2)));
+    assert_eq!(**old, 1);
+
+    match packed.into_inner() {
+        Owned::Single(value) => assert_eq!(**value, 2),
+        _ => panic!("expected `Single`"),
+    }
+}
+
+#[test]
+fn test_view() {
+    let expr = Expr(7);
+    let packed = Pack::new(Node::Binary {
+        lhs: &expr,
+        op: U4::new(3).unwrap(),
+    });
+    match packed.view() {
+        PackedNodeRef::Binary { lhs, op } => {
+            assert_eq!(lhs.get(), &expr);
+            assert_eq!(op.get().get(), 3);
+        }
+        _ => panic!("expected `Binary`"),
+    }
+
+    let packed = Pack::new(Owned::Pair {
+        value: Box::new(Rc::new(4)),
+        flag: true,
+    });
+    match packed.view() {
+        PackedOwnedRef::Pair { flag, .. } => assert!(flag.get()),
+        PackedOwnedRef::Single(_) | PackedOwnedRef::Nothing => panic!("expected `Pair`"),
+    }
+    drop(packed.into_inner());
+}
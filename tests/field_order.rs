@@ -0,0 +1,71 @@
+use ptrpack::bitstart::{BitStart, DefaultStart};
+use ptrpack::{Pack, Packable, RawPackedBits, SubPack};
+
+/// A pointer-like value with 3 unused low bits, whose high bits are set.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+struct Address(usize);
+
+unsafe impl<S: BitStart> Packable<S> for Address {
+    type Packed = SubPack<S, Address>;
+
+    const WIDTH: u32 = usize::BITS - 3;
+
+    unsafe fn store(self, p: &mut RawPackedBits<S, Self>) {
+        p.write_high_bits(self.0)
+    }
+
+    unsafe fn load(p: &RawPackedBits<S, Self>) -> Self {
+        Address(p.read_high_bits())
+    }
+}
+
+// Pointer-like fields don't need to come first: a field's `BEFORE` bits are
+// always taken from its unused low bits, so its high bits are preserved.
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Trailing {
+    flag: bool,
+    #[ptrpack(bits = 2)]
+    kind: u8,
+    address: Address,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Between<'a> {
+    flag: bool,
+    value: &'a u64,
+    other: bool,
+}
+
+#[test]
+fn test_high_bits_after_other_fields() {
+    let address = Address(usize::MAX << 3);
+    let value = Trailing {
+        flag: true,
+        kind: 2,
+        address,
+    };
+    assert_eq!(<Trailing as Packable<DefaultStart>>::WIDTH, usize::BITS);
+
+    let mut packed = Pack::new(value);
+    assert_eq!(packed.get(), value);
+    assert_eq!(packed.address(), address);
+
+    let address = Address(1 << (usize::BITS - 1) | 0b1000);
+    packed.address_mut().set(address);
+    assert_eq!(packed.address(), address);
+    assert!(packed.flag());
+    assert_eq!(packed.kind(), 2);
+}
+
+#[test]
+fn test_pointer_between_fields() {
+    let target = 5u64;
+    let value = Between {
+        flag: true,
+        value: &target,
+        other: true,
+    };
+    let packed = Pack::new(value);
+    assert_eq!(packed.get(), value);
+    assert!(std::ptr::eq(packed.value(), &target));
+}
@@ -0,0 +1,108 @@
+use ptrpack::{Pack, PackFlags, Packable};
+
+#[derive(PackFlags, Debug, Eq, PartialEq, Copy, Clone)]
+struct Perms {
+    read: bool,
+    write: bool,
+    exec: bool,
+}
+
+const READ: Perms = Perms {
+    read: true,
+    ..Perms::NONE
+};
+const WRITE: Perms = Perms {
+    write: true,
+    ..Perms::NONE
+};
+const EXEC: Perms = Perms {
+    exec: true,
+    ..Perms::NONE
+};
+
+#[test]
+fn test_set_operations() {
+    let mut perms = Pack::new(READ);
+    assert!(perms.contains(READ));
+    assert!(!perms.contains(Perms::ALL));
+    assert!(perms.intersects(Perms::ALL));
+    assert!(!perms.is_empty());
+
+    perms.insert(EXEC);
+    assert_eq!(
+        perms.get(),
+        Perms {
+            read: true,
+            write: false,
+            exec: true,
+        }
+    );
+    assert_eq!(perms.union(WRITE), Perms::ALL);
+    assert_eq!(perms.intersection(WRITE), Perms::NONE);
+    assert_eq!(perms.difference(READ), EXEC);
+
+    perms.toggle(Perms::ALL);
+    assert_eq!(perms.get(), WRITE);
+    perms.remove(WRITE);
+    assert!(perms.is_empty());
+    perms.insert(Perms::ALL);
+    assert!(perms.is_all());
+}
+
+#[test]
+fn test_iter_and_debug() {
+    let perms = Pack::new(Perms {
+        read: true,
+        write: false,
+        exec: true,
+    });
+    assert_eq!(perms.iter().collect::<Vec<_>>(), ["read", "exec"]);
+    assert_eq!(format!("{:?}", *perms), "read | exec");
+    assert_eq!(format!("{:?}", *Pack::new(Perms::NONE)), "(empty)");
+}
+
+#[derive(PackFlags, Debug, Eq, PartialEq, Copy, Clone)]
+struct NoFlags;
+
+#[test]
+fn test_no_flags() {
+    let flags = Pack::new(NoFlags);
+    assert!(flags.is_empty());
+    assert!(flags.is_all());
+    assert_eq!(flags.iter().count(), 0);
+    assert_eq!(format!("{:?}", *flags), "(empty)");
+}
+
+#[repr(align(8))]
+struct Inode(#[allow(dead_code)] u64);
+
+#[derive(Packable)]
+struct Handle<'a> {
+    inode: &'a Inode,
+    perms: Perms,
+}
+
+#[test]
+fn test_as_field() {
+    let inode = Inode(0);
+    let mut handle = Pack::new(Handle {
+        inode: &inode,
+        perms: READ,
+    });
+
+    handle.perms_mut().insert(WRITE);
+    assert_eq!(
+        handle.perms(),
+        Perms {
+            exec: false,
+            ..Perms::ALL
+        }
+    );
+    assert!(handle.get_perms().contains(WRITE));
+    assert_eq!(format!("{:?}", handle.get_perms()), "read | write");
+    assert!(core::ptr::eq(handle.inode(), &inode));
+
+    handle.perms_mut().remove(Perms::ALL);
+    assert!(handle.get_perms().is_empty());
+    assert!(core::ptr::eq(handle.inode(), &inode));
+}
@@ -0,0 +1,100 @@
+use ptrpack::bitstart::DefaultStart;
+use ptrpack::{Pack, Packable};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+pub trait Node: Debug + Eq {}
+
+#[repr(align(64))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+struct Leaf(u32);
+
+impl Node for Leaf {}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Slot<'a, T: Node, const K: usize>
+where
+    T: 'a,
+{
+    node: &'a T,
+    #[ptrpack(bits = 2)]
+    kind: u8,
+    marker: PhantomData<[u8; K]>,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+enum Choice<'a, T, const K: usize>
+where
+    T: Node + 'a,
+{
+    Node(&'a T),
+    Small(Slot<'a, Leaf, K>),
+    Empty,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Callback<T> {
+    id: u16,
+    callback: PhantomData<for<'a> fn(&'a T) -> &'a u8>,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Defaults<T = u8, const N: u32 = 4> {
+    marker: PhantomData<T>,
+    bits: ptrpack::impls::UInt<N>,
+}
+
+#[test]
+fn test_lifetimes_and_bounds() {
+    let leaf = Leaf(1);
+    let slot: Slot<Leaf, 3> = Slot {
+        node: &leaf,
+        kind: 2,
+        marker: PhantomData,
+    };
+    assert_eq!(
+        <Slot<Leaf, 3> as Packable<DefaultStart>>::WIDTH,
+        <&Leaf as Packable<DefaultStart>>::WIDTH + 2
+    );
+    let packed = Pack::new(slot);
+    assert_eq!(packed.get(), slot);
+    assert_eq!(packed.node(), &leaf);
+    assert_eq!(packed.kind(), 2);
+}
+
+#[test]
+fn test_enum_generics() {
+    let leaf = Leaf(1);
+    let small = Slot {
+        node: &leaf,
+        kind: 1,
+        marker: PhantomData,
+    };
+    for &value in &[
+        Choice::<Leaf, 2>::Node(&leaf),
+        Choice::Small(small),
+        Choice::Empty,
+    ] {
+        assert_eq!(Pack::new(value).get(), value);
+    }
+}
+
+#[test]
+fn test_higher_ranked_field() {
+    let value: Callback<u32> = Callback {
+        id: 7,
+        callback: PhantomData,
+    };
+    assert_eq!(<Callback<u32> as Packable<DefaultStart>>::WIDTH, 16);
+    assert_eq!(Pack::new(value).id(), 7);
+}
+
+#[test]
+fn test_defaulted_params() {
+    let value: Defaults = Defaults {
+        marker: PhantomData,
+        bits: ptrpack::impls::U4::new(9).unwrap(),
+    };
+    assert_eq!(<Defaults as Packable<DefaultStart>>::WIDTH, 4);
+    assert_eq!(Pack::new(value).get(), value);
+}
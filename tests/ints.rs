@@ -0,0 +1,80 @@
+use std::mem::ManuallyDrop;
+use std::num::{NonZeroI16, NonZeroU32, NonZeroU8};
+
+use ptrpack::bitstart::DefaultStart;
+use ptrpack::{Pack, Packable};
+
+#[derive(Packable)]
+struct Ints {
+    a: u8,
+    b: i16,
+    c: char,
+    d: bool,
+    e: i8,
+}
+
+#[test]
+fn test_native_ints() {
+    let packed = Pack::new(Ints {
+        a: 0xfe,
+        b: -1234,
+        c: '\u{10FFFF}',
+        d: true,
+        e: -128,
+    });
+
+    assert_eq!(packed.a(), 0xfe);
+    assert_eq!(packed.b(), -1234);
+    assert_eq!(packed.c(), '\u{10FFFF}');
+    assert!(packed.d());
+    assert_eq!(packed.e(), -128);
+
+    let Ints { a, b, c, d, e } = packed.into_inner();
+    assert_eq!((a, b, c, d, e), (0xfe, -1234, '\u{10FFFF}', true, -128));
+}
+
+#[derive(Packable)]
+struct Niches {
+    a: Option<NonZeroU8>,
+    b: Option<NonZeroI16>,
+}
+
+#[test]
+fn test_nonzero_niche() {
+    assert_eq!(<Option<NonZeroU32> as Packable<DefaultStart>>::WIDTH, 32);
+
+    let mut packed = Pack::new(Niches {
+        a: NonZeroU8::new(3),
+        b: None,
+    });
+
+    assert_eq!(packed.a(), NonZeroU8::new(3));
+    assert_eq!(packed.b(), None);
+
+    packed.a_mut().set(None);
+    packed.b_mut().set(NonZeroI16::new(-7));
+
+    assert_eq!(packed.a(), None);
+    assert_eq!(packed.b(), NonZeroI16::new(-7));
+
+    let x = 5u32;
+    assert_eq!(Pack::new(Some(&x)).into_inner(), Some(&x));
+    assert_eq!(Pack::new(None::<&u32>).into_inner(), None);
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn test_u64() {
+    let packed = Pack::new(u64::MAX - 1);
+    assert_eq!(packed.into_inner(), u64::MAX - 1);
+}
+
+#[test]
+#[should_panic(expected = "invalid packed `char` value")]
+fn test_invalid_char() {
+    // A surrogate code point, which isn't a valid `char`.
+    // Not dropped, as dropping loads the value again.
+    let packed =
+        ManuallyDrop::new(unsafe { Pack::<char>::from_bits(0xD800 << (usize::BITS - 21)) });
+    packed.get();
+}
@@ -0,0 +1,66 @@
+use ptrpack::{Pack, Packable};
+
+mod common;
+use common::width;
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+enum Slot<'a, T> {
+    Empty,
+    Full(&'a T),
+    Tombstone,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+enum Single<'a> {
+    Only(&'a u32),
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+enum Lonely {
+    Unit,
+}
+
+#[derive(Packable)]
+enum Void {}
+
+#[test]
+fn test_all_unit() {
+    assert_eq!(width::<Direction>(), 2);
+    for &dir in &[
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ] {
+        assert_eq!(Pack::new(dir).get(), dir);
+    }
+}
+
+#[test]
+fn test_mixed() {
+    assert_eq!(width::<Slot<u64>>(), width::<&u64>() + 2);
+
+    let value = 7u64;
+    for &slot in &[Slot::Empty, Slot::Full(&value), Slot::Tombstone] {
+        assert_eq!(Pack::new(slot).get(), slot);
+    }
+}
+
+#[test]
+fn test_single_variant() {
+    assert_eq!(width::<Single>(), width::<&u32>());
+    assert_eq!(width::<Lonely>(), 0);
+    assert_eq!(width::<Void>(), 0);
+
+    let value = 3;
+    assert_eq!(Pack::new(Single::Only(&value)).get(), Single::Only(&value));
+    assert_eq!(Pack::new(Lonely::Unit).get(), Lonely::Unit);
+}
@@ -0,0 +1,75 @@
+use ptrpack::bitstart::DefaultStart;
+use ptrpack::impls::Narrow;
+use ptrpack::{Pack, Packable};
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Entry {
+    #[ptrpack(bits = 12)]
+    offset: u32,
+    #[ptrpack(bits = 5)]
+    delta: i8,
+    dirty: bool,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+enum Event {
+    Move(#[ptrpack(bits = 10)] i16, #[ptrpack(bits = 10)] i16),
+    Key {
+        #[ptrpack(bits = 7)]
+        code: u8,
+    },
+}
+
+#[test]
+fn test_narrow_fields() {
+    assert_eq!(<Entry as Packable<DefaultStart>>::WIDTH, 12 + 5 + 1);
+    assert_eq!(<Event as Packable<DefaultStart>>::WIDTH, 20 + 1);
+    // A narrowed integer may use the full width of its type.
+    assert_eq!(<Narrow<i8, 8> as Packable<DefaultStart>>::WIDTH, 8);
+
+    let entry = Entry {
+        offset: 4095,
+        delta: -16,
+        dirty: true,
+    };
+    let packed = Pack::try_new(entry).unwrap();
+    assert_eq!(packed.offset(), 4095);
+    assert_eq!(packed.delta(), -16);
+    assert_eq!(packed.get(), entry);
+
+    let event = Event::Move(-512, 511);
+    assert_eq!(Pack::try_new(event).unwrap().get(), event);
+    let event = Event::Key { code: 127 };
+    assert_eq!(Pack::try_new(event).unwrap().get(), event);
+}
+
+#[test]
+fn test_narrow_overflow() {
+    let entry = Entry {
+        offset: 4096,
+        delta: 0,
+        dirty: false,
+    };
+    assert_eq!(Pack::try_new(entry).unwrap_err(), entry);
+
+    let entry = Entry {
+        offset: 0,
+        delta: 16,
+        dirty: false,
+    };
+    assert!(Pack::try_new(entry).is_err());
+
+    assert!(Pack::try_new(Event::Move(-513, 0)).is_err());
+    assert!(Pack::try_new(Event::Key { code: 128 }).is_err());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "does not fit")]
+fn test_narrow_debug_assertion() {
+    Pack::new(Entry {
+        offset: 1 << 20,
+        delta: 0,
+        dirty: false,
+    });
+}
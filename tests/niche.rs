@@ -0,0 +1,78 @@
+use ptrpack::bitstart::DefaultStart;
+use ptrpack::{Pack, Packable};
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Object(u64);
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(niche)]
+pub enum Value<'a> {
+    Null,
+    Undefined,
+    Obj(&'a Object),
+    True,
+    False,
+}
+
+#[derive(Packable)]
+#[ptrpack(niche)]
+pub enum Owned {
+    Empty,
+    Full(Box<Object>),
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+pub struct WithSpare<'a> {
+    value: Value<'a>,
+    flag: bool,
+}
+
+#[test]
+fn test_niche_width() {
+    assert_eq!(
+        <Value as Packable<DefaultStart>>::WIDTH,
+        <&Object as Packable<DefaultStart>>::WIDTH
+    );
+    assert_eq!(
+        <WithSpare as Packable<DefaultStart>>::WIDTH,
+        <&Object as Packable<DefaultStart>>::WIDTH + 1
+    );
+}
+
+#[test]
+fn test_niche_values() {
+    let object = Object(42);
+    let values = [
+        Value::Null,
+        Value::Undefined,
+        Value::Obj(&object),
+        Value::True,
+        Value::False,
+    ];
+    for &value in &values {
+        let packed = Pack::new(value);
+        assert_eq!(packed.get(), value);
+
+        let packed = Pack::new(WithSpare { value, flag: true });
+        assert_eq!(packed.get(), WithSpare { value, flag: true });
+    }
+
+    let packed = Pack::new(Value::Obj(&object));
+    assert!(packed.is_obj());
+    assert!(!packed.is_null());
+    assert_eq!(packed.as_obj().unwrap().get(), &object);
+
+    let packed = Pack::new(Value::False);
+    assert!(packed.is_false());
+    assert!(packed.as_obj().is_none());
+}
+
+#[test]
+fn test_niche_owned() {
+    let packed = Pack::new(Owned::Full(Box::new(Object(7))));
+    match packed.into_inner() {
+        Owned::Full(object) => assert_eq!(*object, Object(7)),
+        Owned::Empty => panic!("expected `Full`"),
+    }
+    assert!(matches!(Pack::new(Owned::Empty).into_inner(), Owned::Empty));
+}
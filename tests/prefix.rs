@@ -0,0 +1,183 @@
+use ptrpack::bitstart::{BitStart, DefaultStart};
+use ptrpack::impls::U8;
+use ptrpack::layout::VariantLayout;
+use ptrpack::{Pack, Packable};
+
+mod common;
+use common::raw_bits;
+
+#[repr(align(8))]
+#[derive(Debug, Eq, PartialEq)]
+struct Object(u64);
+
+// A V8-style value representation: pointers get a single tag bit, leaving the
+// longer tags for the narrower variants.
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(tag = "prefix")]
+enum Value<'a> {
+    Object(&'a Object),
+    Small(i32),
+    Byte(U8),
+    Null,
+}
+
+// Tags depend on the width of each payload, not on declaration order.
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(tag = "prefix")]
+enum Reversed<'a> {
+    Null,
+    Byte(U8),
+    Small(i32),
+    Object(&'a Object),
+}
+
+// Equally wide payloads still need tags of different lengths when there are
+// three of them.
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(tag = "prefix")]
+enum Balanced {
+    A(U8),
+    B(U8),
+    C(U8),
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(tag = "prefix")]
+enum Single {
+    Only(bool),
+}
+
+#[test]
+fn test_prefix_width() {
+    let ptr_width = <&Object as Packable<DefaultStart>>::WIDTH;
+    assert_eq!(<Value as Packable<DefaultStart>>::WIDTH, ptr_width + 1);
+    assert_eq!(<Reversed as Packable<DefaultStart>>::WIDTH, ptr_width + 1);
+    assert_eq!(<Balanced as Packable<DefaultStart>>::WIDTH, 10);
+    assert_eq!(<Single as Packable<DefaultStart>>::WIDTH, 1);
+}
+
+#[test]
+fn test_prefix_roundtrip() {
+    let object = Object(0x1234);
+    for &(tag, tag_width, value) in [
+        (0b0, 1, Value::Object(&object)),
+        (0b10, 2, Value::Small(-5)),
+        (0b110, 3, Value::Byte(U8::new(0xab).unwrap())),
+        (0b111, 3, Value::Null),
+    ]
+    .iter()
+    {
+        let packed = Pack::new(value);
+        assert_eq!(packed.get(), value);
+        assert_eq!(raw_bits(&packed) >> (usize::BITS - tag_width), tag);
+    }
+
+    let packed = Pack::new(Value::Object(&object));
+    assert!(packed.is_object());
+    assert_eq!(packed.as_object().unwrap().get(), &object);
+    assert!(!packed.is_null());
+
+    let packed = Pack::new(Single::Only(true));
+    assert_eq!(packed.get(), Single::Only(true));
+}
+
+#[test]
+fn test_prefix_layout() {
+    let variants = PackedValue::<DefaultStart>::VARIANTS;
+    let tags: Vec<_> = variants
+        .iter()
+        .map(|v| (v.name, v.tag, v.tag_width))
+        .collect();
+    assert_eq!(
+        tags,
+        [
+            ("Object", 0b0, 1),
+            ("Small", 0b10, 2),
+            ("Byte", 0b110, 3),
+            ("Null", 0b111, 3),
+        ]
+    );
+    for variant in variants {
+        assert_eq!(variant.tag_start, DefaultStart::START);
+    }
+    assert_eq!(variants[1].fields[0].start, DefaultStart::START - 2);
+    assert_eq!(variants[1].fields[0].width, 32);
+}
+
+#[test]
+fn test_prefix_assignment() {
+    let tags = |variants: &[VariantLayout]| -> Vec<_> {
+        variants
+            .iter()
+            .map(|v| (v.name, v.tag, v.tag_width))
+            .collect()
+    };
+    assert_eq!(
+        tags(PackedReversed::<DefaultStart>::VARIANTS),
+        [
+            ("Null", 0b110, 3),
+            ("Byte", 0b111, 3),
+            ("Small", 0b10, 2),
+            ("Object", 0b0, 1),
+        ]
+    );
+    assert_eq!(
+        tags(PackedBalanced::<DefaultStart>::VARIANTS),
+        [("A", 0b10, 2), ("B", 0b11, 2), ("C", 0b0, 1)]
+    );
+
+    let object = Object(0x5678);
+    for &value in &[
+        Reversed::Null,
+        Reversed::Byte(U8::new(0x12).unwrap()),
+        Reversed::Small(i32::MIN),
+        Reversed::Object(&object),
+    ] {
+        assert_eq!(Pack::new(value).get(), value);
+    }
+    for &value in &[
+        Balanced::A(U8::new(1).unwrap()),
+        Balanced::B(U8::new(2).unwrap()),
+        Balanced::C(U8::new(3).unwrap()),
+    ] {
+        assert_eq!(Pack::new(value).get(), value);
+    }
+}
+
+#[test]
+fn test_prefix_overwrite() {
+    // Tags must be replaced, not merged, when a value is overwritten.
+    let object = Object(0x9abc);
+    let values = [
+        Value::Object(&object),
+        Value::Small(-1),
+        Value::Byte(U8::MAX),
+        Value::Null,
+    ];
+    for &from in &values {
+        for &to in &values {
+            let mut packed = Pack::new(from);
+            packed.set(to);
+            assert_eq!(packed.get(), to);
+            let mut packed = Pack::new(from);
+            assert_eq!(packed.replace(to), from);
+            assert_eq!(packed.get(), to);
+        }
+    }
+
+    let values = [
+        Balanced::A(U8::MAX),
+        Balanced::B(U8::MAX),
+        Balanced::C(U8::MAX),
+    ];
+    for &from in &values {
+        for &to in &values {
+            let mut packed = Pack::new(from);
+            packed.set(to);
+            assert_eq!(packed.get(), to);
+            let mut packed = Pack::new(from);
+            assert_eq!(packed.replace(to), from);
+            assert_eq!(packed.get(), to);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use ptrpack::impls::U3;
+use ptrpack::{Pack, Packable};
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(repr = "u16")]
+struct Status {
+    ready: bool,
+    error: bool,
+    mode: U3,
+    #[ptrpack(bits = 3)]
+    offset: i8,
+    count: u8,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(repr = "u8")]
+struct Flags(bool, bool, #[ptrpack(skip)] (), U3);
+
+const EMPTY: PackedStatusRepr = PackedStatusRepr::from_repr(0);
+
+// The helpers can build and inspect bitfields in constants.
+const INITIAL: PackedStatusRepr = PackedStatusRepr::new(Status {
+    ready: true,
+    error: false,
+    mode: U3::MAX,
+    offset: -2,
+    count: 7,
+});
+const INITIAL_OFFSET: i8 = INITIAL.offset();
+const FAILED: Status = INITIAL.with_error(true).with_offset(-4).get();
+
+#[test]
+fn test_repr_layout() {
+    // Fields are allocated from the most significant bit of the integer.
+    let status = PackedStatusRepr::from_repr(0b1000_0000_0000_0000);
+    assert!(status.ready());
+    assert!(!status.error());
+
+    let status = EMPTY.with_count(0xff);
+    assert_eq!(status.to_repr(), 0x00ff);
+    let status = EMPTY.with_mode(U3::MAX);
+    assert_eq!(status.to_repr(), 0b0011_1000_0000_0000);
+}
+
+#[test]
+fn test_repr_roundtrip() {
+    let value = Status {
+        ready: true,
+        error: false,
+        mode: U3::new(5).unwrap(),
+        offset: -3,
+        count: 200,
+    };
+    let mut status = PackedStatusRepr::new(value);
+    assert_eq!(status.get(), value);
+    assert_eq!(PackedStatusRepr::from_repr(status.to_repr()), status);
+    assert_eq!(status.offset(), -3);
+
+    status.set_offset(3);
+    status.set_error(true);
+    assert_eq!(
+        status.get(),
+        Status {
+            error: true,
+            offset: 3,
+            ..value
+        }
+    );
+
+    // The struct is still packable as usual.
+    assert_eq!(Pack::new(value).get(), value);
+}
+
+#[test]
+fn test_repr_tuple() {
+    let flags = PackedFlagsRepr::new(Flags(true, false, (), U3::new(6).unwrap()));
+    assert_eq!(flags.to_repr(), 0b1011_0000);
+    assert_eq!(flags.with_1(true)._3(), U3::new(6).unwrap());
+}
+
+#[test]
+fn test_repr_const() {
+    assert_eq!(INITIAL.to_repr(), 0b1011_1110_0000_0111);
+    assert_eq!(INITIAL_OFFSET, -2);
+    assert_eq!(
+        FAILED,
+        Status {
+            ready: true,
+            error: true,
+            mode: U3::MAX,
+            offset: -4,
+            count: 7,
+        }
+    );
+}
@@ -0,0 +1,94 @@
+use std::cell::Cell;
+
+use ptrpack::bitstart::DefaultStart;
+use ptrpack::layout::FieldLayout;
+use ptrpack::{Pack, Packable};
+
+#[derive(Packable, Debug)]
+struct Cached<'a> {
+    value: &'a u64,
+    #[ptrpack(skip)]
+    cache: Cell<Option<u32>>,
+    #[ptrpack(default = "7")]
+    seven: u8,
+    #[ptrpack(skip, default = "3")]
+    three: u8,
+    flag: bool,
+}
+
+#[derive(Packable, Debug, PartialEq)]
+enum Tagged {
+    Plain(bool),
+    Labelled(#[ptrpack(skip)] String, bool),
+}
+
+#[test]
+fn test_skipped_fields() {
+    assert_eq!(
+        <Cached as Packable<DefaultStart>>::WIDTH,
+        <&u64 as Packable<DefaultStart>>::WIDTH + 1
+    );
+
+    let value = 5;
+    let packed = Pack::new(Cached {
+        value: &value,
+        cache: Cell::new(Some(10)),
+        seven: 200,
+        three: 100,
+        flag: true,
+    });
+    assert!(packed.flag());
+
+    let cached = packed.into_inner();
+    assert_eq!(cached.value, &5);
+    assert_eq!(cached.cache.get(), None);
+    assert_eq!(cached.seven, 7);
+    assert_eq!(cached.three, 3);
+    assert!(cached.flag);
+}
+
+#[test]
+fn test_skipped_variant_fields() {
+    let packed = Pack::new(Tagged::Labelled("label".to_owned(), true));
+    match packed.view() {
+        PackedTaggedRef::Labelled(flag) => assert!(flag.get()),
+        PackedTaggedRef::Plain(_) => panic!("expected `Labelled`"),
+    }
+    assert_eq!(packed.into_inner(), Tagged::Labelled(String::new(), true));
+}
+
+#[test]
+fn test_layout_table() {
+    let ptr_width = <&u64 as Packable<DefaultStart>>::WIDTH;
+    let layout = PackedCached::<DefaultStart>::LAYOUT;
+    assert_eq!(
+        layout,
+        &[
+            FieldLayout {
+                name: "value",
+                start: usize::BITS,
+                width: ptr_width,
+            },
+            FieldLayout {
+                name: "cache",
+                start: usize::BITS - ptr_width,
+                width: 0,
+            },
+            FieldLayout {
+                name: "seven",
+                start: usize::BITS - ptr_width,
+                width: 0,
+            },
+            FieldLayout {
+                name: "three",
+                start: usize::BITS - ptr_width,
+                width: 0,
+            },
+            FieldLayout {
+                name: "flag",
+                start: usize::BITS - ptr_width,
+                width: 1,
+            },
+        ]
+    );
+}
@@ -0,0 +1,120 @@
+use ptrpack::bitstart::DefaultStart;
+use ptrpack::impls::{U4, U8};
+use ptrpack::{Pack, Packable};
+
+mod common;
+use common::raw_bits;
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(tag = "high")]
+enum High<'a> {
+    Int(U8),
+    Ptr(&'a u64),
+    Nothing,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(tag = "low")]
+enum Low<'a> {
+    Int(U8),
+    Ptr(&'a u64),
+    Nothing,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(tag = "bit:4")]
+enum Fixed {
+    Int(U8),
+    Flag(bool),
+    Nothing,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Holder {
+    value: Fixed,
+    low: U4,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(tag = "bit:0")]
+enum FixedPtr<'a> {
+    Ptr(&'a u64),
+    Int(U8),
+}
+
+#[test]
+fn test_tag_high() {
+    let width = <High as Packable<DefaultStart>>::WIDTH;
+    assert_eq!(width, <&u64 as Packable<DefaultStart>>::WIDTH + 2);
+
+    let value = 0x1234u64;
+    for (idx, &high) in [
+        High::Int(U8::new(0xab).unwrap()),
+        High::Ptr(&value),
+        High::Nothing,
+    ]
+    .iter()
+    .enumerate()
+    {
+        let packed = Pack::new(high);
+        assert_eq!(packed.get(), high);
+        assert_eq!(raw_bits(&packed) >> (usize::BITS - 2), idx);
+    }
+
+    let packed = Pack::new(High::Ptr(&value));
+    assert_eq!(packed.as_ptr().unwrap().get(), &value);
+}
+
+#[test]
+fn test_tag_low() {
+    let value = 0x1234u64;
+    for (idx, &low) in [
+        Low::Int(U8::new(0xab).unwrap()),
+        Low::Ptr(&value),
+        Low::Nothing,
+    ]
+    .iter()
+    .enumerate()
+    {
+        let packed = Pack::new(low);
+        assert_eq!(packed.get(), low);
+        let width = <Low as Packable<DefaultStart>>::WIDTH;
+        assert_eq!((raw_bits(&packed) >> (usize::BITS - width)) & 0b11, idx);
+    }
+}
+
+#[test]
+fn test_tag_bit() {
+    // Everything down to the tag is used by the enum, leaving the bits below
+    // it for later fields.
+    let width = <Fixed as Packable<DefaultStart>>::WIDTH;
+    assert_eq!(width, usize::BITS - 4);
+
+    for (idx, &value) in [
+        Fixed::Int(U8::new(0xab).unwrap()),
+        Fixed::Flag(true),
+        Fixed::Nothing,
+    ]
+    .iter()
+    .enumerate()
+    {
+        let packed = Pack::new(value);
+        assert_eq!(packed.get(), value);
+        assert_eq!((raw_bits(&packed) >> 4) & 0b11, idx);
+
+        let holder = Holder {
+            value,
+            low: U4::MAX,
+        };
+        let packed = Pack::new(holder);
+        assert_eq!(packed.get(), holder);
+        assert_eq!(raw_bits(&packed) & 0b11_1111, idx << 4 | 0b1111);
+    }
+
+    let value = 0x1234u64;
+    let packed = Pack::new(FixedPtr::Ptr(&value));
+    assert_eq!(raw_bits(&packed) & 0b1, 0);
+    assert_eq!(packed.as_ptr().unwrap().get(), &value);
+    let packed = Pack::new(FixedPtr::Int(U8::new(7).unwrap()));
+    assert_eq!(raw_bits(&packed) & 0b1, 1);
+}
@@ -0,0 +1,76 @@
+use ptrpack::bitstart::DefaultStart;
+use ptrpack::{Pack, Packable, SubPack};
+use std::marker::PhantomData;
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+struct Node {
+    value: u32,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(transparent)]
+struct NodeRef<'a>(&'a Node);
+
+// A typed ID, with a marker which isn't stored.
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+#[ptrpack(transparent)]
+struct Id<T> {
+    index: u16,
+    #[ptrpack(skip)]
+    marker: PhantomData<T>,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Marked<'a> {
+    node: NodeRef<'a>,
+    marked: bool,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Edge {
+    from: Id<Node>,
+    to: Id<Node>,
+}
+
+#[test]
+fn test_transparent_width() {
+    assert_eq!(
+        <NodeRef as Packable<DefaultStart>>::WIDTH,
+        <&Node as Packable<DefaultStart>>::WIDTH
+    );
+    assert_eq!(<Id<Node> as Packable<DefaultStart>>::WIDTH, 16);
+}
+
+#[test]
+fn test_transparent_packed() {
+    let node = Node { value: 5 };
+    let other = Node { value: 6 };
+    let mut packed = Pack::new(NodeRef(&node));
+    let sub: &SubPack<DefaultStart, NodeRef> = &packed;
+    assert_eq!(sub.get(), NodeRef(&node));
+
+    packed.set(NodeRef(&other));
+    assert_eq!(packed.get().0.value, 6);
+
+    let marked = Pack::new(Marked {
+        node: NodeRef(&node),
+        marked: true,
+    });
+    assert_eq!(marked.node(), NodeRef(&node));
+    assert!(marked.marked());
+
+    let (from, to) = (
+        Id {
+            index: 42,
+            marker: PhantomData,
+        },
+        Id {
+            index: 7,
+            marker: PhantomData,
+        },
+    );
+    let mut edge = Pack::new(Edge { from, to });
+    assert_eq!(edge.from(), from);
+    edge.to_mut().set(from);
+    assert_eq!(edge.get(), Edge { from, to: from });
+}
@@ -0,0 +1,106 @@
+use std::convert::TryFrom;
+
+use ptrpack::impls::{UInt, U0, U3, U4, U5};
+use ptrpack::{Pack, Packable};
+
+#[test]
+fn test_uint_bounds() {
+    assert_eq!(U0::MAX.get(), 0);
+    assert_eq!(U5::MAX.get(), 31);
+    assert_eq!(UInt::<{ usize::BITS }>::MAX.get(), usize::MAX);
+
+    assert!(U5::new(31).is_some());
+    assert!(U5::new(32).is_none());
+    assert!(U0::new(1).is_none());
+}
+
+#[test]
+fn test_uint_arithmetic() {
+    let a = U3::new(6).unwrap();
+    let b = U3::new(3).unwrap();
+
+    assert_eq!(a.checked_add(b), None);
+    assert_eq!(a.wrapping_add(b).get(), 1);
+    assert_eq!(a.saturating_add(b), U3::MAX);
+
+    assert_eq!(b.checked_sub(a), None);
+    assert_eq!(b.wrapping_sub(a).get(), 5);
+    assert_eq!(b.saturating_sub(a), U3::MIN);
+
+    assert_eq!(a.checked_mul(b), None);
+    assert_eq!(a.wrapping_mul(b).get(), 2);
+    assert_eq!(a.saturating_mul(b), U3::MAX);
+}
+
+#[test]
+fn test_uint_conversions() {
+    assert_eq!(U5::try_from(31).map(usize::from), Ok(31));
+    assert!(U5::try_from(32).is_err());
+    assert_eq!(usize::from(U3::MAX), 7);
+}
+
+#[derive(Packable)]
+struct Counters {
+    low: U4,
+    high: U4,
+}
+
+#[test]
+fn test_subpack_arithmetic() {
+    let mut packed = Pack::new(Counters {
+        low: U4::new(14).unwrap(),
+        high: U4::new(1).unwrap(),
+    });
+
+    packed.low_mut().increment();
+    assert_eq!(packed.low().get(), 15);
+    assert_eq!(packed.high().get(), 1);
+
+    packed.low_mut().wrapping_add_assign(U4::new(2).unwrap());
+    assert_eq!(packed.low().get(), 1);
+    assert_eq!(packed.high().get(), 1);
+
+    packed.high_mut().saturating_sub_assign(U4::new(3).unwrap());
+    assert_eq!(packed.high(), U4::MIN);
+    packed.high_mut().wrapping_sub_assign(U4::new(1).unwrap());
+    assert_eq!(packed.high(), U4::MAX);
+    packed.high_mut().saturating_add_assign(U4::new(1).unwrap());
+    assert_eq!(packed.high(), U4::MAX);
+    assert_eq!(packed.low().get(), 1);
+
+    packed.low_mut().decrement();
+    assert_eq!(packed.low(), U4::MIN);
+}
+
+#[test]
+#[should_panic(expected = "overflow")]
+fn test_subpack_increment_overflow() {
+    let mut packed = Pack::new(Counters {
+        low: U4::MAX,
+        high: U4::MIN,
+    });
+    packed.low_mut().increment();
+}
+
+#[cfg(target_pointer_width = "64")]
+#[derive(Packable)]
+struct Counter {
+    flag: bool,
+    count: UInt<48>,
+    small: U5,
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn test_wide_uint() {
+    let count = UInt::<48>::new((1 << 48) - 1).unwrap();
+    let small = U5::new(17).unwrap();
+    let packed = Pack::new(Counter {
+        flag: true,
+        count,
+        small,
+    });
+    assert!(packed.flag());
+    assert_eq!(packed.count(), count);
+    assert_eq!(packed.small(), small);
+}
@@ -0,0 +1,220 @@
+use ptrpack::impls::U3;
+use ptrpack::visit::{BitsKind, PackVisitor, Visitable};
+use ptrpack::{Pack, Packable};
+
+#[repr(align(256))]
+struct Aligned(#[allow(dead_code)] u8);
+
+#[derive(Packable)]
+struct Header {
+    #[ptrpack(bits = 4)]
+    level: i8,
+    kind: U3,
+    flag: bool,
+}
+
+#[derive(Packable)]
+enum Shape<'a> {
+    Empty,
+    Ref(&'a Aligned),
+    Boxed(Box<Aligned>),
+    Header(Header),
+}
+
+#[derive(Packable)]
+struct Link<'a> {
+    next: Option<&'a Aligned>,
+    mark: bool,
+}
+
+#[derive(Packable, Copy, Clone)]
+#[ptrpack(transparent)]
+struct Id(#[ptrpack(bits = 12)] u16);
+
+#[derive(Packable)]
+struct HasId {
+    id: Id,
+    flag: bool,
+}
+
+#[derive(Packable, Copy, Clone)]
+#[ptrpack(repr = "u8")]
+struct Status {
+    #[ptrpack(bits = 3)]
+    code: u8,
+    ready: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Field(&'static str),
+    Variant(&'static str),
+    Bits(BitsKind, usize),
+}
+
+#[derive(Default)]
+struct Recorder(Vec<Event>);
+
+impl PackVisitor for Recorder {
+    fn visit_field(&mut self, name: &'static str, value: &dyn Visitable) {
+        self.0.push(Event::Field(name));
+        value.visit(self);
+    }
+
+    fn visit_variant(&mut self, name: &'static str) {
+        self.0.push(Event::Variant(name));
+    }
+
+    fn visit_bits(&mut self, kind: BitsKind, value: usize) {
+        self.0.push(Event::Bits(kind, value));
+    }
+}
+
+fn record(value: &dyn Visitable) -> Vec<Event> {
+    let mut recorder = Recorder::default();
+    value.visit(&mut recorder);
+    recorder.0
+}
+
+#[test]
+fn test_struct() {
+    let header = Pack::new(Header {
+        level: -2,
+        kind: U3::new(5).unwrap(),
+        flag: true,
+    });
+    assert_eq!(
+        record(&*header),
+        [
+            Event::Field("level"),
+            Event::Bits(BitsKind::Signed(4), -2isize as usize),
+            Event::Field("kind"),
+            Event::Bits(BitsKind::Unsigned(3), 5),
+            Event::Field("flag"),
+            Event::Bits(BitsKind::Bool, 1),
+        ]
+    );
+}
+
+#[test]
+fn test_enum() {
+    assert_eq!(record(&*Pack::new(Shape::Empty)), [Event::Variant("Empty")]);
+
+    let target = Aligned(0);
+    let addr = &target as *const Aligned as usize;
+    assert_eq!(
+        record(&*Pack::new(Shape::Ref(&target))),
+        [
+            Event::Variant("Ref"),
+            Event::Field("0"),
+            Event::Bits(BitsKind::Ref, addr),
+        ]
+    );
+
+    let boxed = Box::new(Aligned(1));
+    let addr = &*boxed as *const Aligned as usize;
+    assert_eq!(
+        record(&*Pack::new(Shape::Boxed(boxed))),
+        [
+            Event::Variant("Boxed"),
+            Event::Field("0"),
+            Event::Bits(BitsKind::Box, addr),
+        ]
+    );
+
+    let header = Header {
+        level: 3,
+        kind: U3::new(0).unwrap(),
+        flag: false,
+    };
+    assert_eq!(
+        record(&*Pack::new(Shape::Header(header))),
+        [
+            Event::Variant("Header"),
+            Event::Field("0"),
+            Event::Field("level"),
+            Event::Bits(BitsKind::Signed(4), 3),
+            Event::Field("kind"),
+            Event::Bits(BitsKind::Unsigned(3), 0),
+            Event::Field("flag"),
+            Event::Bits(BitsKind::Bool, 0),
+        ]
+    );
+}
+
+#[test]
+fn test_option() {
+    let target = Aligned(0);
+    let addr = &target as *const Aligned as usize;
+
+    let mut recorder = Recorder::default();
+    Pack::new(Link {
+        next: Some(&target),
+        mark: false,
+    })
+    .visit(&mut recorder);
+    assert_eq!(
+        recorder.0,
+        [
+            Event::Field("next"),
+            Event::Variant("Some"),
+            Event::Field("0"),
+            Event::Bits(BitsKind::Ref, addr),
+            Event::Field("mark"),
+            Event::Bits(BitsKind::Bool, 0),
+        ]
+    );
+
+    let link = Pack::new(Link {
+        next: None,
+        mark: true,
+    });
+    assert_eq!(
+        record(&*link),
+        [
+            Event::Field("next"),
+            Event::Variant("None"),
+            Event::Field("mark"),
+            Event::Bits(BitsKind::Bool, 1),
+        ]
+    );
+}
+
+#[test]
+fn test_transparent() {
+    let has_id = Pack::new(HasId {
+        id: Id(0xabc),
+        flag: true,
+    });
+    assert_eq!(
+        record(&*has_id),
+        [
+            Event::Field("id"),
+            Event::Bits(BitsKind::Unsigned(12), 0xabc),
+            Event::Field("flag"),
+            Event::Bits(BitsKind::Bool, 1),
+        ]
+    );
+
+    assert_eq!(
+        record(&*Pack::new(Id(7))),
+        [Event::Bits(BitsKind::Unsigned(12), 7)]
+    );
+}
+
+#[test]
+fn test_repr() {
+    let status = PackedStatusRepr::new(Status {
+        code: 5,
+        ready: true,
+    });
+    assert_eq!(
+        record(&status),
+        [
+            Event::Field("code"),
+            Event::Bits(BitsKind::Unsigned(3), 5),
+            Event::Field("ready"),
+            Event::Bits(BitsKind::Bool, 1),
+        ]
+    );
+}
@@ -0,0 +1,82 @@
+use std::marker::PhantomData;
+
+use ptrpack::bitstart::{BitStart, DefaultStart, NextStart};
+use ptrpack::{Pack, Packable};
+
+mod common;
+use common::width;
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Marker;
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct Empty {}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+struct AllZero<T> {
+    unit: (),
+    marker: Marker,
+    phantom: PhantomData<T>,
+}
+
+#[derive(Packable)]
+struct Mixed<'a, T> {
+    lifetime: PhantomData<&'a mut T>,
+    flag: bool,
+    unit: (),
+    empty: Empty,
+    value: &'a u16,
+}
+
+#[derive(Packable, Debug, Eq, PartialEq, Copy, Clone)]
+enum WithUnit {
+    A(()),
+    B(Marker),
+}
+
+#[test]
+fn test_zero_width() {
+    assert_eq!(width::<()>(), 0);
+    assert_eq!(width::<PhantomData<String>>(), 0);
+    assert_eq!(width::<Marker>(), 0);
+    assert_eq!(width::<Empty>(), 0);
+    assert_eq!(width::<AllZero<u32>>(), 0);
+    assert_eq!(width::<Mixed<u8>>(), 1 + width::<&u16>());
+
+    assert_eq!(
+        <NextStart<DefaultStart, AllZero<u32>> as BitStart>::START,
+        DefaultStart::START
+    );
+    assert_eq!(
+        <NextStart<NextStart<DefaultStart, ()>, Marker> as BitStart>::START,
+        DefaultStart::START
+    );
+}
+
+#[test]
+fn test_zero_width_fields() {
+    assert_eq!(Pack::new(Marker).into_inner(), Marker);
+    assert_eq!(Pack::new(Empty {}).into_inner(), Empty {});
+
+    let all_zero = AllZero::<u32> {
+        unit: (),
+        marker: Marker,
+        phantom: PhantomData,
+    };
+    assert_eq!(Pack::new(all_zero).into_inner(), all_zero);
+
+    let value = 12u16;
+    let packed = Pack::new(Mixed::<u8> {
+        lifetime: PhantomData,
+        flag: true,
+        unit: (),
+        empty: Empty {},
+        value: &value,
+    });
+    assert!(packed.flag());
+    assert_eq!(packed.value(), &value);
+    assert_eq!(packed.unit(), ());
+
+    assert_eq!(Pack::new(WithUnit::A(())).get(), WithUnit::A(()));
+    assert_eq!(Pack::new(WithUnit::B(Marker)).get(), WithUnit::B(Marker));
+}